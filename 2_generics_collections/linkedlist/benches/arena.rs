@@ -0,0 +1,76 @@
+//! Compare the arena-backed list with the boxed `LinkedList`.
+//!
+//! Run with `cargo bench`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use linkedlist::{arena::ArenaList, LinkedList};
+
+const SIZES: [usize; 3] = [100, 10_000, 1_000_000];
+
+fn push_pop(c: &mut Criterion) {
+    let mut group = c.benchmark_group("push then pop");
+    for size in SIZES.iter() {
+        group.bench_with_input(BenchmarkId::new("boxed", size), size, |b, &size| {
+            b.iter(|| {
+                let mut list = LinkedList::new();
+                for i in 0..size {
+                    list.push(i);
+                }
+                while let Some(element) = list.pop() {
+                    black_box(element);
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("arena", size), size, |b, &size| {
+            b.iter(|| {
+                let mut list = ArenaList::new();
+                for i in 0..size {
+                    list.push(i);
+                }
+                while let Some(element) = list.pop() {
+                    black_box(element);
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn iterate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("iterate");
+    for size in SIZES.iter() {
+        let boxed: LinkedList<_> = (0..*size).collect();
+        group.bench_with_input(BenchmarkId::new("boxed", size), &boxed, |b, list| {
+            b.iter(|| list.iter().sum::<usize>())
+        });
+        let arena: ArenaList<_> = (0..*size).collect();
+        group.bench_with_input(BenchmarkId::new("arena", size), &arena, |b, list| {
+            b.iter(|| list.iter().sum::<usize>())
+        });
+    }
+    group.finish();
+}
+
+/// A list which keeps the same size: the arena reuses its slots instead of allocating
+fn churn(c: &mut Criterion) {
+    let mut group = c.benchmark_group("churn");
+    let size = 10_000;
+    let mut boxed: LinkedList<_> = (0..size).collect();
+    group.bench_function("boxed", |b| {
+        b.iter(|| {
+            let element = boxed.pop().unwrap();
+            boxed.push(black_box(element));
+        })
+    });
+    let mut arena: ArenaList<_> = (0..size).collect();
+    group.bench_function("arena", |b| {
+        b.iter(|| {
+            let element = arena.pop().unwrap();
+            arena.push(black_box(element));
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, push_pop, iterate, churn);
+criterion_main!(benches);
@@ -0,0 +1,493 @@
+//! A linked list whose nodes are stored in one contiguous arena instead of being
+//! boxed one by one.
+//!
+//! Nodes link to each other by their index in the arena, and the slots of removed
+//! nodes are reused by the next pushes, so that a list which stays the same size
+//! stops allocating. Every push returns a `Handle` to its element, which stays valid
+//! until that element is removed.
+
+use std::iter::FromIterator;
+use std::marker::PhantomData;
+
+/// A reference to an element of an `ArenaList`, valid until the element is removed.
+///
+/// Each slot of the arena counts how many times it was reused, so that a handle to
+/// a removed element never gives access to the element which took its slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Handle {
+    index: usize,
+    generation: u64,
+}
+
+struct Node<T> {
+    element: T,
+    prev: Option<usize>,
+    next: Option<usize>,
+}
+
+enum Slot<T> {
+    Occupied(Node<T>),
+    /// A slot waiting to be reused, linked to the next free one
+    Free(Option<usize>),
+}
+
+struct Entry<T> {
+    generation: u64,
+    slot: Slot<T>,
+}
+
+/// A stack with the API of `LinkedList`, backed by an arena. The nodes are linked
+/// both ways, so that any element can be removed through its handle in O(1).
+pub struct ArenaList<T> {
+    entries: Vec<Entry<T>>,
+    head: Option<usize>,
+    /// The last node of the list, so that extending it is O(1)
+    tail: Option<usize>,
+    /// The first free slot of the arena
+    free: Option<usize>,
+    len: usize,
+}
+
+impl<T> ArenaList<T> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Create a list which can hold `capacity` elements without allocating
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: Vec::with_capacity(capacity),
+            head: None,
+            tail: None,
+            free: None,
+            len: 0,
+        }
+    }
+
+    /// Get the number of elements, in O(1)
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Push an element at the head of the list, in O(1), reusing a free slot of the
+    /// arena if there is one.
+    ///
+    /// # Returns
+    /// A handle to the element.
+    pub fn push(&mut self, element: T) -> Handle {
+        let index = self.insert(Node {
+            element,
+            prev: None,
+            next: self.head,
+        });
+        match self.head {
+            Some(head) => self.node_mut(head).prev = Some(index),
+            None => self.tail = Some(index),
+        }
+        self.head = Some(index);
+        self.handle(index)
+    }
+
+    /// Push an element at the end of the list, in O(1)
+    ///
+    /// # Returns
+    /// A handle to the element.
+    pub fn push_back(&mut self, element: T) -> Handle {
+        let index = self.insert(Node {
+            element,
+            prev: self.tail,
+            next: None,
+        });
+        match self.tail {
+            Some(tail) => self.node_mut(tail).next = Some(index),
+            None => self.head = Some(index),
+        }
+        self.tail = Some(index);
+        self.handle(index)
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let index = self.head?;
+        Some(self.unlink(index))
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.head.map(|index| &self.node(index).element)
+    }
+
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
+        let index = self.head?;
+        Some(&mut self.node_mut(index).element)
+    }
+
+    /// Get a handle to the head of the list
+    pub fn head(&self) -> Option<Handle> {
+        self.head.map(|index| self.handle(index))
+    }
+
+    /// Get the element of a handle, or `None` if it was removed
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        match self.slot(handle)? {
+            Slot::Occupied(node) => Some(&node.element),
+            Slot::Free(_) => None,
+        }
+    }
+
+    /// Get the element of a handle mutably, or `None` if it was removed
+    pub fn get_mut(&mut self, handle: Handle) -> Option<&mut T> {
+        let entry = self.entries.get_mut(handle.index)?;
+        match &mut entry.slot {
+            Slot::Occupied(node) if entry.generation == handle.generation => {
+                Some(&mut node.element)
+            }
+            _ => None,
+        }
+    }
+
+    /// Remove the element of a handle, wherever it is in the list, in O(1)
+    ///
+    /// # Returns
+    /// The element, or `None` if it was already removed.
+    pub fn remove(&mut self, handle: Handle) -> Option<T> {
+        self.get(handle)?;
+        Some(self.unlink(handle.index))
+    }
+
+    /// Remove every element, keeping the memory of the arena
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    /// Iterate over references to the elements, from the head of the list
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            list: self,
+            next: self.head,
+        }
+    }
+
+    /// Iterate over mutable references to the elements, from the head of the list
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            entries: self.entries.as_mut_ptr(),
+            next: self.head,
+            marker: PhantomData,
+        }
+    }
+
+    fn slot(&self, handle: Handle) -> Option<&Slot<T>> {
+        self.entries
+            .get(handle.index)
+            .filter(|entry| entry.generation == handle.generation)
+            .map(|entry| &entry.slot)
+    }
+
+    /// Store a node in a free slot of the arena, or in a new one
+    fn insert(&mut self, node: Node<T>) -> usize {
+        let index = match self.free {
+            Some(index) => {
+                let entry = &mut self.entries[index];
+                if let Slot::Free(next_free) = entry.slot {
+                    self.free = next_free;
+                }
+                entry.slot = Slot::Occupied(node);
+                index
+            }
+            None => {
+                self.entries.push(Entry {
+                    generation: 0,
+                    slot: Slot::Occupied(node),
+                });
+                self.entries.len() - 1
+            }
+        };
+        self.len += 1;
+        index
+    }
+
+    fn handle(&self, index: usize) -> Handle {
+        Handle {
+            index,
+            generation: self.entries[index].generation,
+        }
+    }
+
+    fn node(&self, index: usize) -> &Node<T> {
+        match &self.entries[index].slot {
+            Slot::Occupied(node) => node,
+            Slot::Free(_) => unreachable!("a link points to a free slot"),
+        }
+    }
+
+    fn node_mut(&mut self, index: usize) -> &mut Node<T> {
+        match &mut self.entries[index].slot {
+            Slot::Occupied(node) => node,
+            Slot::Free(_) => unreachable!("a link points to a free slot"),
+        }
+    }
+
+    /// Take the node at `index` out of the list, and put its slot on the free list
+    fn unlink(&mut self, index: usize) -> T {
+        let entry = &mut self.entries[index];
+        let node = match std::mem::replace(&mut entry.slot, Slot::Free(self.free)) {
+            Slot::Occupied(node) => node,
+            Slot::Free(_) => unreachable!("only occupied slots are unlinked"),
+        };
+        entry.generation += 1;
+        self.free = Some(index);
+
+        match node.prev {
+            Some(prev) => self.node_mut(prev).next = node.next,
+            None => self.head = node.next,
+        }
+        match node.next {
+            Some(next) => self.node_mut(next).prev = node.prev,
+            None => self.tail = node.prev,
+        }
+        self.len -= 1;
+        node.element
+    }
+}
+
+impl<T> Default for ArenaList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Owning iterator, popping the elements from the head of the list
+pub struct IntoIter<T>(ArenaList<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.pop()
+    }
+}
+
+pub struct Iter<'a, T> {
+    list: &'a ArenaList<T>,
+    next: Option<usize>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.next.map(|index| {
+            let node = self.list.node(index);
+            self.next = node.next;
+            &node.element
+        })
+    }
+}
+
+pub struct IterMut<'a, T> {
+    entries: *mut Entry<T>,
+    next: Option<usize>,
+    marker: PhantomData<&'a mut T>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        self.next.map(|index| {
+            // SAFETY: the list is mutably borrowed for 'a, so the arena is neither
+            // moved nor resized, and following the links yields every node only once
+            let entry = unsafe { &mut *self.entries.add(index) };
+            match &mut entry.slot {
+                Slot::Occupied(node) => {
+                    self.next = node.next;
+                    &mut node.element
+                }
+                Slot::Free(_) => unreachable!("a link points to a free slot"),
+            }
+        })
+    }
+}
+
+impl<T> IntoIterator for ArenaList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a ArenaList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut ArenaList<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+/// Add the elements at the end of the list, in order
+impl<T> Extend<T> for ArenaList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        // Free slots are reused before the arena grows
+        let free = self.entries.len() - self.len;
+        self.entries
+            .reserve(iter.size_hint().0.saturating_sub(free));
+        for element in iter {
+            self.push_back(element);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for ArenaList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_pop_peek() {
+        let mut list = ArenaList::new();
+        assert!(list.peek().is_none());
+        assert_eq!(list.pop(), None);
+
+        list.push(1);
+        list.push(2);
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.peek(), Some(&2));
+        *list.peek_mut().unwrap() = 20;
+        assert_eq!(list.pop(), Some(20));
+        assert_eq!(list.pop(), Some(1));
+        assert_eq!(list.pop(), None);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn iter() {
+        let list: ArenaList<_> = (1..4).collect();
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![&1, &2, &3]);
+        let mut sum = 0;
+        for element in &list {
+            sum += element;
+        }
+        assert_eq!(sum, 6);
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn iter_mut() {
+        let mut list: ArenaList<_> = (1..4).collect();
+        let removed = list.push(0);
+        list.remove(removed);
+        for element in &mut list {
+            *element *= 10;
+        }
+        let mut iter = list.iter_mut();
+        *iter.next().unwrap() += 1;
+        *iter.next().unwrap() += 2;
+        *iter.next().unwrap() += 3;
+        assert!(iter.next().is_none());
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![11, 22, 33]);
+    }
+
+    #[test]
+    fn push_back() {
+        let mut list = ArenaList::new();
+        let last = list.push_back(2);
+        list.push(1);
+        list.extend(3..5);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+
+        list.pop();
+        assert_eq!(list.remove(last), Some(2));
+        list.extend(vec![5]);
+        list.push(0);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![0, 3, 4, 5]);
+        while list.pop().is_some() {}
+        list.push_back(6);
+        assert_eq!(list.peek(), Some(&6));
+    }
+
+    #[test]
+    fn slots_are_reused() {
+        let mut list = ArenaList::new();
+        for i in 0..3 {
+            list.push(i);
+        }
+        list.pop();
+        list.pop();
+        list.push(3);
+        list.push(4);
+        list.push(5);
+        assert_eq!(list.entries.len(), 4);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![5, 4, 3, 0]);
+    }
+
+    #[test]
+    fn handles() {
+        let mut list = ArenaList::new();
+        let first = list.push("first");
+        let second = list.push("second");
+        let third = list.push("third");
+        assert_eq!(list.head(), Some(third));
+        assert_eq!(list.get(first), Some(&"first"));
+
+        *list.get_mut(second).unwrap() = "middle";
+        assert_eq!(list.remove(second), Some("middle"));
+        assert_eq!(list.remove(second), None);
+        assert_eq!(list.get(second), None);
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![&"third", &"first"]);
+
+        // The slot of the removed element is reused, but its handle stays invalid
+        let fourth = list.push("fourth");
+        assert_eq!(fourth.index, second.index);
+        assert_eq!(list.get(second), None);
+        assert!(list.get_mut(second).is_none());
+        assert_eq!(list.get(fourth), Some(&"fourth"));
+
+        // Handles of the other elements are not affected
+        assert_eq!(list.get(first), Some(&"first"));
+        assert_eq!(list.remove(first), Some("first"));
+        assert_eq!(list.remove(third), Some("third"));
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![&"fourth"]);
+        assert_eq!(list.len(), 1);
+    }
+
+    #[test]
+    fn clear() {
+        let mut list: ArenaList<_> = (0..5).collect();
+        let handle = list.head().unwrap();
+        list.clear();
+        assert!(list.is_empty());
+        assert_eq!(list.get(handle), None);
+        list.push(1);
+        assert_eq!(list.entries.len(), 5);
+
+        // Extending only grows the arena by what the free slots cannot hold
+        let capacity = list.entries.capacity();
+        list.extend(0..4);
+        assert_eq!(list.entries.capacity(), capacity);
+        list.extend(0..10);
+        assert_eq!(list.entries.len(), 15);
+    }
+}
@@ -0,0 +1,191 @@
+//! A stack which can be pushed to and popped from by many threads at once, without
+//! locking: a Treiber stack.
+//!
+//! Threads race to swap the head of the stack with a compare-and-swap, and retry
+//! when another thread won. A popped node may still be read by threads which loaded
+//! it as the head just before, so it is only freed once every thread has moved on,
+//! using the epoch-based reclamation of `crossbeam_epoch`.
+
+use crossbeam_epoch::{self as epoch, Atomic, Owned};
+use std::{mem::ManuallyDrop, ptr, sync::atomic::Ordering};
+
+struct Node<T> {
+    /// Moved out by the thread which pops the node, and never dropped with the node
+    element: ManuallyDrop<T>,
+    next: Atomic<Node<T>>,
+}
+
+pub struct Stack<T> {
+    head: Atomic<Node<T>>,
+}
+
+// Elements are moved in and out of the stack, but never shared between threads
+unsafe impl<T: Send> Send for Stack<T> {}
+unsafe impl<T: Send> Sync for Stack<T> {}
+
+impl<T> Stack<T> {
+    pub fn new() -> Self {
+        Self {
+            head: Atomic::null(),
+        }
+    }
+
+    pub fn push(&self, element: T) {
+        let mut node = Owned::new(Node {
+            element: ManuallyDrop::new(element),
+            next: Atomic::null(),
+        });
+        let guard = epoch::pin();
+
+        loop {
+            let head = self.head.load(Ordering::Relaxed, &guard);
+            node.next.store(head, Ordering::Relaxed);
+            // Release, so that the node is fully written before other threads see it
+            match self.head.compare_exchange(
+                head,
+                node,
+                Ordering::Release,
+                Ordering::Relaxed,
+                &guard,
+            ) {
+                Ok(_) => return,
+                Err(error) => node = error.new,
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let guard = epoch::pin();
+
+        loop {
+            let head = self.head.load(Ordering::Acquire, &guard);
+            // SAFETY: the node cannot be freed while this thread is pinned
+            let node = unsafe { head.as_ref() }?;
+            let next = node.next.load(Ordering::Relaxed, &guard);
+
+            if self
+                .head
+                .compare_exchange(head, next, Ordering::Relaxed, Ordering::Relaxed, &guard)
+                .is_ok()
+            {
+                // SAFETY: only the thread whose compare-and-swap unlinked the node
+                // takes its element, and the node is freed once no thread can
+                // read it any more, without dropping the element again.
+                unsafe {
+                    guard.defer_destroy(head);
+                    return Some(ManuallyDrop::into_inner(ptr::read(&node.element)));
+                }
+            }
+        }
+    }
+
+    /// Check whether the stack is empty. Other threads may push or pop right after.
+    pub fn is_empty(&self) -> bool {
+        let guard = epoch::pin();
+        self.head.load(Ordering::Acquire, &guard).is_null()
+    }
+}
+
+/// Drop the elements left one after the other, as `LinkedList` does
+impl<T> Drop for Stack<T> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+impl<T> Default for Stack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+    };
+
+    #[test]
+    fn push_pop() {
+        let stack = Stack::new();
+        assert!(stack.is_empty());
+        assert_eq!(stack.pop(), None);
+        stack.push(1);
+        stack.push(2);
+        assert!(!stack.is_empty());
+        assert_eq!(stack.pop(), Some(2));
+        assert_eq!(stack.pop(), Some(1));
+        assert_eq!(stack.pop(), None);
+    }
+
+    #[test]
+    fn drop_elements() {
+        let counter = Arc::new(());
+        let stack = Stack::new();
+        for _ in 0..10 {
+            stack.push(Arc::clone(&counter));
+        }
+        stack.pop();
+        drop(stack);
+        // Popped nodes may be freed later, but their elements were moved out
+        assert_eq!(Arc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn stress() {
+        const PRODUCERS: usize = 8;
+        const CONSUMERS: usize = 8;
+        const PER_PRODUCER: usize = 10_000;
+        const TOTAL: usize = PRODUCERS * PER_PRODUCER;
+
+        let stack = Arc::new(Stack::new());
+        let popped = Arc::new(AtomicUsize::new(0));
+
+        let producers: Vec<_> = (0..PRODUCERS)
+            .map(|producer| {
+                let stack = Arc::clone(&stack);
+                thread::spawn(move || {
+                    for i in 0..PER_PRODUCER {
+                        stack.push(producer * PER_PRODUCER + i);
+                    }
+                })
+            })
+            .collect();
+        let consumers: Vec<_> = (0..CONSUMERS)
+            .map(|_| {
+                let stack = Arc::clone(&stack);
+                let popped = Arc::clone(&popped);
+                thread::spawn(move || {
+                    let mut seen = Vec::new();
+                    while popped.load(Ordering::Relaxed) < TOTAL {
+                        if let Some(element) = stack.pop() {
+                            popped.fetch_add(1, Ordering::Relaxed);
+                            seen.push(element);
+                        } else {
+                            thread::yield_now();
+                        }
+                    }
+                    seen
+                })
+            })
+            .collect();
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        let mut times = vec![0; TOTAL];
+        for consumer in consumers {
+            for element in consumer.join().unwrap() {
+                times[element] += 1;
+            }
+        }
+
+        assert!(times.iter().all(|&count| count == 1));
+        assert!(stack.is_empty());
+    }
+}
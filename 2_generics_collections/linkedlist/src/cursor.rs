@@ -0,0 +1,322 @@
+use std::{marker::PhantomData, mem};
+
+use super::{Link, LinkedList};
+
+/// A cursor over a `LinkedList`, which can edit the list in place.
+///
+/// The cursor points either at an element of the list, or past its end. As the list
+/// is singly linked, it can only move forward. The cursor holds the node before its
+/// current one, so that editing the list around it is done in O(1).
+pub struct CursorMut<'a, T> {
+    list: &'a mut LinkedList<T>,
+    /// The node before the current one, `None` at the head of the list
+    prev: Link<T>,
+    /// The current node, `None` past the end of the list
+    current: Link<T>,
+    index: usize,
+}
+
+impl<'a, T> CursorMut<'a, T> {
+    pub(crate) fn new(list: &'a mut LinkedList<T>) -> Self {
+        Self {
+            current: list.head,
+            list,
+            prev: None,
+            index: 0,
+        }
+    }
+
+    /// Link `link` in place of the current node: after the previous node, or at the
+    /// head of the list
+    fn relink(&mut self, link: Link<T>) {
+        match self.prev {
+            Some(prev) => unsafe { (*prev.as_ptr()).next = link },
+            None => self.list.head = link,
+        }
+    }
+
+    /// Get the position of the cursor: the index of the current element, or the
+    /// length of the list past its end.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Move to the next element, in O(1).
+    ///
+    /// # Returns
+    /// `false` if the cursor was already past the end of the list, where it stays.
+    pub fn move_next(&mut self) -> bool {
+        match self.current {
+            Some(current) => {
+                self.prev = Some(current);
+                self.current = unsafe { (*current.as_ptr()).next };
+                self.index += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Get the current element, or `None` past the end of the list
+    pub fn current(&mut self) -> Option<&mut T> {
+        self.current
+            .map(|node| unsafe { &mut (*node.as_ptr()).element })
+    }
+
+    /// Get the element after the current one, if any
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        self.current
+            .and_then(|node| unsafe { (*node.as_ptr()).next })
+            .map(|node| unsafe { &mut (*node.as_ptr()).element })
+    }
+
+    /// Insert an element before the current one, in O(1). The cursor stays on the
+    /// current element, or past the end of the list.
+    pub fn insert_before(&mut self, element: T) {
+        let node = LinkedList::allocate(element, self.current);
+        self.relink(Some(node));
+        if self.current.is_none() {
+            self.list.tail = Some(node);
+        }
+        self.list.len += 1;
+        self.prev = Some(node);
+        self.index += 1;
+    }
+
+    /// Insert an element after the current one, in O(1). The cursor stays on the
+    /// current element. Past the end of the list, the element is added at the end,
+    /// and the cursor stays past it.
+    pub fn insert_after(&mut self, element: T) {
+        match self.current {
+            Some(current) => unsafe {
+                let node = LinkedList::allocate(element, (*current.as_ptr()).next);
+                (*current.as_ptr()).next = Some(node);
+                if self.list.tail == Some(current) {
+                    self.list.tail = Some(node);
+                }
+                self.list.len += 1;
+            },
+            None => self.insert_before(element),
+        }
+    }
+
+    /// Remove the current element, in O(1). The cursor moves to the next element.
+    ///
+    /// # Returns
+    /// The element removed, or `None` past the end of the list.
+    pub fn remove_current(&mut self) -> Option<T> {
+        let current = self.current?;
+        // SAFETY: the node was allocated as a box, and is unlinked right away
+        let node = unsafe { Box::from_raw(current.as_ptr()) };
+        self.relink(node.next);
+        if node.next.is_none() {
+            self.list.tail = self.prev;
+        }
+        self.list.len -= 1;
+        self.current = node.next;
+        Some(node.element)
+    }
+
+    /// Cut the list before the current element, in O(1). The cursor is then past the
+    /// end of the list.
+    ///
+    /// # Returns
+    /// The current element and every one after it, which are removed from the list.
+    pub fn split(&mut self) -> LinkedList<T> {
+        let head = match self.current.take() {
+            Some(head) => head,
+            None => return LinkedList::new(),
+        };
+        self.relink(None);
+        LinkedList {
+            head: Some(head),
+            tail: mem::replace(&mut self.list.tail, self.prev),
+            len: mem::replace(&mut self.list.len, self.index) - self.index,
+            marker: PhantomData,
+        }
+    }
+
+    /// Cut the list after the current element, in O(1).
+    ///
+    /// # Returns
+    /// Every element after the current one, which are removed from the list. Past
+    /// the end of the list, the returned list is empty.
+    pub fn split_after(&mut self) -> LinkedList<T> {
+        let (current, head) = match self.current {
+            Some(current) => match unsafe { (*current.as_ptr()).next.take() } {
+                Some(head) => (current, head),
+                None => return LinkedList::new(),
+            },
+            None => return LinkedList::new(),
+        };
+        LinkedList {
+            head: Some(head),
+            tail: self.list.tail.replace(current),
+            len: mem::replace(&mut self.list.len, self.index + 1) - self.index - 1,
+            marker: PhantomData,
+        }
+    }
+
+    /// Insert every element of `other` before the current one, in order, in O(1).
+    /// The cursor stays on the current element, or past the end of the list.
+    pub fn splice_before(&mut self, mut other: LinkedList<T>) {
+        let chain = match other.take_nodes() {
+            Some(chain) => chain,
+            None => return,
+        };
+        unsafe { (*chain.tail.as_ptr()).next = self.current };
+        self.relink(Some(chain.head));
+        if self.current.is_none() {
+            self.list.tail = Some(chain.tail);
+        }
+        self.list.len += chain.len;
+        self.prev = Some(chain.tail);
+        self.index += chain.len;
+    }
+
+    /// Insert every element of `other` after the current one, in order, in O(1).
+    /// The cursor stays on the current element. Past the end of the list, the
+    /// elements are added at the end, and the cursor stays past them.
+    pub fn splice_after(&mut self, mut other: LinkedList<T>) {
+        let current = match self.current {
+            Some(current) => current,
+            None => return self.splice_before(other),
+        };
+        let chain = match other.take_nodes() {
+            Some(chain) => chain,
+            None => return,
+        };
+        unsafe {
+            (*chain.tail.as_ptr()).next = (*current.as_ptr()).next;
+            (*current.as_ptr()).next = Some(chain.head);
+        }
+        if self.list.tail == Some(current) {
+            self.list.tail = Some(chain.tail);
+        }
+        self.list.len += chain.len;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::assert_consistent;
+    use super::*;
+
+    fn elements(list: &LinkedList<i32>) -> Vec<i32> {
+        list.iter().copied().collect()
+    }
+
+    #[test]
+    fn move_next() {
+        let mut list: LinkedList<_> = (1..4).collect();
+        let mut cursor = list.cursor_mut();
+        assert_eq!(cursor.index(), 0);
+        assert_eq!(cursor.current(), Some(&mut 1));
+        assert_eq!(cursor.peek_next(), Some(&mut 2));
+
+        assert!(cursor.move_next());
+        assert!(cursor.move_next());
+        assert_eq!(cursor.current(), Some(&mut 3));
+        assert_eq!(cursor.peek_next(), None);
+        *cursor.current().unwrap() = 30;
+
+        assert!(cursor.move_next());
+        assert_eq!(cursor.index(), 3);
+        assert_eq!(cursor.current(), None);
+        assert!(!cursor.move_next());
+        assert_eq!(cursor.index(), 3);
+        assert_eq!(elements(&list), vec![1, 2, 30]);
+    }
+
+    #[test]
+    fn empty_list() {
+        let mut list = LinkedList::new();
+        let mut cursor = list.cursor_mut();
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.remove_current(), None);
+        assert!(cursor.split_after().peek().is_none());
+        cursor.insert_after(2);
+        cursor.insert_before(3);
+        assert_eq!(cursor.index(), 2);
+        assert_eq!(elements(&list), vec![2, 3]);
+    }
+
+    #[test]
+    fn insert() {
+        let mut list: LinkedList<_> = (1..3).collect();
+        let mut cursor = list.cursor_mut();
+        cursor.insert_before(0);
+        assert_eq!(cursor.current(), Some(&mut 1));
+        assert_eq!(cursor.index(), 1);
+
+        cursor.insert_after(10);
+        assert_eq!(cursor.current(), Some(&mut 1));
+        assert_eq!(cursor.peek_next(), Some(&mut 10));
+
+        while cursor.move_next() {}
+        cursor.insert_before(3);
+        cursor.insert_after(4);
+        assert_eq!(cursor.current(), None);
+        assert_eq!(elements(&list), vec![0, 1, 10, 2, 3, 4]);
+        assert_consistent(&list);
+    }
+
+    #[test]
+    fn remove_current() {
+        let mut list: LinkedList<_> = (1..5).collect();
+        let mut cursor = list.cursor_mut();
+        assert_eq!(cursor.remove_current(), Some(1));
+        assert_eq!(cursor.current(), Some(&mut 2));
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(3));
+        assert_eq!(cursor.remove_current(), Some(4));
+        assert_eq!(cursor.remove_current(), None);
+        assert_consistent(&list);
+        assert_eq!(elements(&list), vec![2]);
+    }
+
+    #[test]
+    fn split() {
+        let mut list: LinkedList<_> = (1..6).collect();
+        let mut cursor = list.cursor_mut();
+        cursor.move_next();
+        let after = cursor.split_after();
+        assert_eq!(cursor.current(), Some(&mut 2));
+        assert_eq!(elements(&after), vec![3, 4, 5]);
+
+        let rest = cursor.split();
+        assert_eq!(cursor.current(), None);
+        assert_eq!(elements(&rest), vec![2]);
+        assert_eq!(elements(&list), vec![1]);
+        assert_consistent(&after);
+        assert_consistent(&rest);
+        assert_consistent(&list);
+    }
+
+    #[test]
+    fn splice() {
+        let mut list: LinkedList<_> = vec![1, 5].into_iter().collect();
+        let mut cursor = list.cursor_mut();
+        cursor.move_next();
+        cursor.splice_before((2..4).collect());
+        assert_eq!(cursor.current(), Some(&mut 5));
+        assert_eq!(cursor.index(), 3);
+
+        cursor.splice_after((6..8).collect());
+        cursor.splice_before(LinkedList::new());
+        cursor.splice_after(LinkedList::new());
+        assert_eq!(cursor.current(), Some(&mut 5));
+
+        cursor.remove_current();
+        cursor.splice_before((4..6).collect());
+        assert_eq!(cursor.current(), Some(&mut 6));
+        while cursor.move_next() {}
+        cursor.splice_after((8..10).collect());
+        assert_eq!(cursor.index(), 9);
+        assert_eq!(elements(&list), (1..10).collect::<Vec<_>>());
+        assert_consistent(&list);
+        list.extend(10..12);
+        assert_eq!(list.len(), 11);
+        assert_eq!(elements(&list), (1..12).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,457 @@
+use std::{iter::FromIterator, marker::PhantomData, ptr::NonNull};
+
+struct Node<T> {
+    element: T,
+    prev: Link<T>,
+    next: Link<T>,
+}
+
+type Link<T> = Option<NonNull<Node<T>>>;
+
+/// A doubly-linked list, which can be pushed to and popped from at both ends in O(1)
+pub struct Deque<T> {
+    front: Link<T>,
+    back: Link<T>,
+    len: usize,
+    /// The deque owns its nodes, which were allocated as boxes
+    marker: PhantomData<Box<Node<T>>>,
+}
+
+// The nodes are only reachable through the deque, which owns them
+unsafe impl<T: Send> Send for Deque<T> {}
+unsafe impl<T: Sync> Sync for Deque<T> {}
+
+impl<T> Deque<T> {
+    pub fn new() -> Self {
+        Self {
+            front: None,
+            back: None,
+            len: 0,
+            marker: PhantomData,
+        }
+    }
+
+    /// Get the number of elements, in O(1)
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push_front(&mut self, element: T) {
+        let node = Self::allocate(element, None, self.front);
+        match self.front {
+            // SAFETY: the front node is owned by this deque and still allocated
+            Some(front) => unsafe { (*front.as_ptr()).prev = Some(node) },
+            None => self.back = Some(node),
+        }
+        self.front = Some(node);
+        self.len += 1;
+    }
+
+    pub fn push_back(&mut self, element: T) {
+        let node = Self::allocate(element, self.back, None);
+        match self.back {
+            // SAFETY: the back node is owned by this deque and still allocated
+            Some(back) => unsafe { (*back.as_ptr()).next = Some(node) },
+            None => self.front = Some(node),
+        }
+        self.back = Some(node);
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.front.map(|front| {
+            // SAFETY: the node was allocated by `allocate`, and is unlinked from the
+            // deque before being freed, so that nothing points to it any more
+            let node = unsafe { Box::from_raw(front.as_ptr()) };
+            self.front = node.next;
+            match self.front {
+                Some(next) => unsafe { (*next.as_ptr()).prev = None },
+                None => self.back = None,
+            }
+            self.len -= 1;
+            node.element
+        })
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.back.map(|back| {
+            // SAFETY: see `pop_front`
+            let node = unsafe { Box::from_raw(back.as_ptr()) };
+            self.back = node.prev;
+            match self.back {
+                Some(prev) => unsafe { (*prev.as_ptr()).next = None },
+                None => self.front = None,
+            }
+            self.len -= 1;
+            node.element
+        })
+    }
+
+    pub fn peek_front(&self) -> Option<&T> {
+        // SAFETY: the node lives as long as the deque is not mutated, which the
+        // borrow of `self` guarantees
+        self.front.map(|node| unsafe { &(*node.as_ptr()).element })
+    }
+
+    pub fn peek_back(&self) -> Option<&T> {
+        // SAFETY: see `peek_front`
+        self.back.map(|node| unsafe { &(*node.as_ptr()).element })
+    }
+
+    pub fn peek_front_mut(&mut self) -> Option<&mut T> {
+        // SAFETY: the mutable borrow of `self` guarantees the reference is unique
+        self.front
+            .map(|node| unsafe { &mut (*node.as_ptr()).element })
+    }
+
+    pub fn peek_back_mut(&mut self) -> Option<&mut T> {
+        // SAFETY: see `peek_front_mut`
+        self.back
+            .map(|node| unsafe { &mut (*node.as_ptr()).element })
+    }
+
+    /// Iterate over references to the elements, from the front of the deque
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            front: self.front,
+            back: self.back,
+            len: self.len,
+            marker: PhantomData,
+        }
+    }
+
+    /// Iterate over mutable references to the elements, from the front of the deque
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            front: self.front,
+            back: self.back,
+            len: self.len,
+            marker: PhantomData,
+        }
+    }
+
+    fn allocate(element: T, prev: Link<T>, next: Link<T>) -> NonNull<Node<T>> {
+        let node = Box::new(Node {
+            element,
+            prev,
+            next,
+        });
+        // SAFETY: a box is never null
+        unsafe { NonNull::new_unchecked(Box::into_raw(node)) }
+    }
+}
+
+impl<T> Drop for Deque<T> {
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+impl<T> Default for Deque<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Owning iterator, popping the elements from either end of the deque
+pub struct IntoIter<T>(Deque<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.0.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+// Both ends of the iterators walk towards each other, and `len` counts the elements
+// between them, so that they stop once they meet.
+
+pub struct Iter<'a, T> {
+    front: Link<T>,
+    back: Link<T>,
+    len: usize,
+    marker: PhantomData<&'a T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.front.map(|node| {
+            self.len -= 1;
+            // SAFETY: the deque is borrowed for 'a, so its nodes outlive the reference
+            let node = unsafe { &*node.as_ptr() };
+            self.front = node.next;
+            &node.element
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.back.map(|node| {
+            self.len -= 1;
+            // SAFETY: see `next`
+            let node = unsafe { &*node.as_ptr() };
+            self.back = node.prev;
+            &node.element
+        })
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+pub struct IterMut<'a, T> {
+    front: Link<T>,
+    back: Link<T>,
+    len: usize,
+    marker: PhantomData<&'a mut T>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.front.map(|node| {
+            self.len -= 1;
+            // SAFETY: the deque is mutably borrowed for 'a, and every node is only
+            // yielded once, by whichever end reaches it first
+            let node = unsafe { &mut *node.as_ptr() };
+            self.front = node.next;
+            &mut node.element
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
+    fn next_back(&mut self) -> Option<&'a mut T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.back.map(|node| {
+            self.len -= 1;
+            // SAFETY: see `next`
+            let node = unsafe { &mut *node.as_ptr() };
+            self.back = node.prev;
+            &mut node.element
+        })
+    }
+}
+
+impl<'a, T> ExactSizeIterator for IterMut<'a, T> {}
+
+impl<T> IntoIterator for Deque<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Deque<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut Deque<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+/// Push the elements at the back of the deque, in order
+impl<T> Extend<T> for Deque<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for element in iter {
+            self.push_back(element);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for Deque<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut deque = Self::new();
+        deque.extend(iter);
+        deque
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create() {
+        let deque = Deque::<i32>::new();
+        assert!(deque.is_empty());
+        assert!(deque.peek_front().is_none());
+        assert!(deque.peek_back().is_none());
+    }
+
+    #[test]
+    fn push_pop_front() {
+        let mut deque = Deque::new();
+        deque.push_front(1);
+        deque.push_front(2);
+        assert_eq!(deque.len(), 2);
+        assert_eq!(deque.pop_front(), Some(2));
+        assert_eq!(deque.pop_front(), Some(1));
+        assert_eq!(deque.pop_front(), None);
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    fn push_pop_back() {
+        let mut deque = Deque::new();
+        deque.push_back(1);
+        deque.push_back(2);
+        assert_eq!(deque.pop_back(), Some(2));
+        assert_eq!(deque.pop_back(), Some(1));
+        assert_eq!(deque.pop_back(), None);
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    fn both_ends() {
+        let mut deque = Deque::new();
+        deque.push_back(2);
+        deque.push_front(1);
+        deque.push_back(3);
+        assert_eq!(deque.len(), 3);
+        assert_eq!(deque.pop_back(), Some(3));
+        assert_eq!(deque.pop_front(), Some(1));
+        assert_eq!(deque.pop_back(), Some(2));
+        assert_eq!(deque.pop_front(), None);
+
+        // The deque is still usable once emptied from both ends
+        deque.push_front(4);
+        assert_eq!(deque.peek_back(), Some(&4));
+        assert_eq!(deque.len(), 1);
+    }
+
+    #[test]
+    fn peek() {
+        let mut deque: Deque<_> = (1..4).collect();
+        assert_eq!(deque.peek_front(), Some(&1));
+        assert_eq!(deque.peek_back(), Some(&3));
+
+        *deque.peek_front_mut().unwrap() = 10;
+        if let Some(back) = deque.peek_back_mut() {
+            *back *= 10;
+        }
+        assert_eq!(deque.pop_front(), Some(10));
+        assert_eq!(deque.pop_back(), Some(30));
+    }
+
+    #[test]
+    fn into_iter() {
+        let deque: Deque<_> = (1..5).collect();
+        let mut iter = deque.into_iter();
+        assert_eq!(iter.len(), 4);
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.next_back(), Some(4));
+        assert_eq!(iter.next(), Some(2));
+        assert_eq!(iter.next_back(), Some(3));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn iter() {
+        let deque: Deque<_> = (1..5).collect();
+        assert_eq!(deque.iter().collect::<Vec<_>>(), vec![&1, &2, &3, &4]);
+        assert_eq!(deque.iter().rev().collect::<Vec<_>>(), vec![&4, &3, &2, &1]);
+
+        let mut iter = deque.iter();
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&4));
+        assert_eq!(iter.len(), 2);
+        assert_eq!(iter.next_back(), Some(&3));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+        assert_eq!(deque.len(), 4);
+    }
+
+    #[test]
+    fn iter_mut() {
+        let mut deque: Deque<_> = (1..4).collect();
+        for element in &mut deque {
+            *element *= 10;
+        }
+        let mut iter = deque.iter_mut();
+        *iter.next_back().unwrap() += 1;
+        *iter.next().unwrap() += 2;
+        *iter.next().unwrap() += 3;
+        assert!(iter.next().is_none());
+        assert!(iter.next_back().is_none());
+        assert_eq!(deque.into_iter().collect::<Vec<_>>(), vec![12, 23, 31]);
+    }
+
+    #[test]
+    fn drop_elements() {
+        use std::rc::Rc;
+
+        let counter = Rc::new(());
+        let mut deque: Deque<_> = (0..10).map(|_| Rc::clone(&counter)).collect();
+        deque.pop_back();
+        let mut iter = deque.into_iter();
+        iter.next();
+        assert_eq!(Rc::strong_count(&counter), 9);
+        drop(iter);
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn drop_long_deque() {
+        // Too slow under Miri, which checks the drop of every node
+        let len = if cfg!(miri) { 100 } else { 1_000_000 };
+        let mut deque = Deque::new();
+        for i in 0..len {
+            deque.push_back(i);
+        }
+        drop(deque);
+    }
+}
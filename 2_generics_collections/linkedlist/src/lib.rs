@@ -1,43 +1,515 @@
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    iter::FromIterator,
+    marker::PhantomData,
+    mem,
+    ptr::NonNull,
+};
+
+pub mod arena;
+pub mod concurrent;
+pub mod cursor;
+pub mod deque;
+pub mod persistent;
+#[cfg(feature = "serde")]
+mod serialize;
+
+pub use cursor::CursorMut;
+
 struct Node<T> {
     element: T,
-    next: Link<Node<T>>,
+    next: Link<T>,
 }
 
+type Link<T> = Option<NonNull<Node<T>>>;
+
+/// Nodes taken out of a list, from `head` to `tail`, still linked together
+struct Chain<T> {
+    head: NonNull<Node<T>>,
+    tail: NonNull<Node<T>>,
+    len: usize,
+}
+
+/// A singly-linked list. It keeps its last node and its length, so that lists are
+/// joined in O(1).
 pub struct LinkedList<T> {
-    head: Link<Node<T>>,
+    head: Link<T>,
+    tail: Link<T>,
+    len: usize,
+    /// The list owns its nodes, which were allocated as boxes
+    marker: PhantomData<Box<Node<T>>>,
 }
 
-type Link<T> = Option<Box<T>>;
+// The nodes are only reachable through the list, which owns them
+unsafe impl<T: Send> Send for LinkedList<T> {}
+unsafe impl<T: Sync> Sync for LinkedList<T> {}
 
 impl<T> LinkedList<T> {
     pub fn new() -> Self {
-        Self { head: None }
+        Self {
+            head: None,
+            tail: None,
+            len: 0,
+            marker: PhantomData,
+        }
     }
 
     pub fn push(&mut self, element: T) {
-        let head = self.head.take();
-        let new_node = Node {
-            element,
-            next: head,
-        };
-        self.head = Some(Box::new(new_node));
+        let node = Self::allocate(element, self.head);
+        if self.tail.is_none() {
+            self.tail = Some(node);
+        }
+        self.head = Some(node);
+        self.len += 1;
     }
 
     pub fn pop(&mut self) -> Option<T> {
-        self.head.take().map(|value| {
-            self.head = value.next;
-            value.element
+        self.head.map(|head| {
+            // SAFETY: the node was allocated as a box, and the list owns it alone
+            let node = unsafe { Box::from_raw(head.as_ptr()) };
+            self.head = node.next;
+            if self.head.is_none() {
+                self.tail = None;
+            }
+            self.len -= 1;
+            node.element
         })
     }
 
     pub fn peek(&self) -> Option<&T> {
-        self.head.as_ref().map(|value| &value.element)
+        self.head.map(|node| unsafe { &(*node.as_ptr()).element })
+    }
+
+    /// Iterate over references to the elements, from the head of the list
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            next: self.head.map(|node| unsafe { &*node.as_ptr() }),
+        }
+    }
+
+    /// Iterate over mutable references to the elements, from the head of the list
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            next: self.head.map(|node| unsafe { &mut *node.as_ptr() }),
+        }
+    }
+
+    /// Get a cursor on the head of the list, to edit it in place
+    pub fn cursor_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut::new(self)
+    }
+
+    /// Get the number of elements, in O(1)
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check whether the list has no element, in O(1)
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    /// Remove every element, in O(n)
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Check whether an element is in the list, in O(n)
+    pub fn contains(&self, element: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.iter().any(|current| current == element)
+    }
+
+    /// Reverse the order of the elements in place, in O(n)
+    pub fn reverse(&mut self) {
+        let mut reversed = None;
+        let mut link = self.head;
+        while let Some(node) = link {
+            unsafe {
+                link = (*node.as_ptr()).next;
+                (*node.as_ptr()).next = reversed;
+            }
+            reversed = Some(node);
+        }
+        self.tail = self.head;
+        self.head = reversed;
+    }
+
+    /// Move every element of `other` at the end of this list, leaving `other` empty,
+    /// in O(1).
+    pub fn append(&mut self, other: &mut Self) {
+        if let Some(chain) = other.take_nodes() {
+            self.link_after_tail(Some(chain.head));
+            self.tail = Some(chain.tail);
+            self.len += chain.len;
+        }
+    }
+
+    /// Split the list in two at the given index, in O(at).
+    ///
+    /// # Returns
+    /// The elements from index `at` onwards, which are removed from this list.
+    ///
+    /// # Panics
+    /// If `at` is greater than the length of the list.
+    pub fn split_off(&mut self, at: usize) -> Self {
+        assert!(at <= self.len, "cannot split off at a nonexistent index");
+        if at == 0 {
+            return mem::take(self);
+        }
+
+        let mut last = self.head.expect("the list is shorter than its length");
+        for _ in 1..at {
+            last = unsafe { (*last.as_ptr()).next }.expect("the list is shorter than its length");
+        }
+        let head = unsafe { (*last.as_ptr()).next.take() };
+        let tail = if head.is_some() {
+            self.tail.replace(last)
+        } else {
+            None
+        };
+        let len = mem::replace(&mut self.len, at) - at;
+        Self {
+            head,
+            tail,
+            len,
+            marker: PhantomData,
+        }
+    }
+
+    /// Keep only the elements for which `keep` returns `true`, in order, in O(n)
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut keep: F) {
+        let mut kept = None;
+        let mut link = self.head;
+        while let Some(node) = link {
+            link = unsafe { (*node.as_ptr()).next };
+            if keep(unsafe { &(*node.as_ptr()).element }) {
+                kept = Some(node);
+                continue;
+            }
+
+            match kept {
+                Some(kept) => unsafe { (*kept.as_ptr()).next = link },
+                None => self.head = link,
+            }
+            if link.is_none() {
+                self.tail = kept;
+            }
+            self.len -= 1;
+            // SAFETY: the node was allocated as a box, and is no longer linked
+            drop(unsafe { Box::from_raw(node.as_ptr()) });
+        }
+    }
+
+    /// Sort the list in place, keeping the order of equal elements.
+    /// See `sort_by`.
+    pub fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        self.sort_by(T::cmp);
+    }
+
+    /// Sort the list in place with a comparison function, keeping the order of
+    /// elements which compare equal.
+    ///
+    /// This is a merge sort of the nodes themselves, which are relinked without any
+    /// allocation, in O(n log n) time and O(log n) stack. If `compare` panics, the
+    /// list is left empty and its elements are leaked.
+    pub fn sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, mut compare: F) {
+        let len = self.len;
+        let head = match self.take_nodes() {
+            Some(chain) => merge_sort(Some(chain.head), len, &mut compare),
+            None => return,
+        };
+
+        let mut tail = head.expect("a sorted list keeps its nodes");
+        while let Some(next) = unsafe { (*tail.as_ptr()).next } {
+            tail = next;
+        }
+        self.head = head;
+        self.tail = Some(tail);
+        self.len = len;
+    }
+
+    /// Add an element at the end of the list, in O(1)
+    pub(crate) fn push_back(&mut self, element: T) {
+        let node = Self::allocate(element, None);
+        self.link_after_tail(Some(node));
+        self.tail = Some(node);
+        self.len += 1;
+    }
+
+    /// Link `link` after the last node of the list, or at its head if it is empty.
+    /// The tail and the length are left to the caller.
+    fn link_after_tail(&mut self, link: Link<T>) {
+        match self.tail {
+            Some(tail) => unsafe { (*tail.as_ptr()).next = link },
+            None => self.head = link,
+        }
+    }
+
+    /// Take every node out of the list, which is left empty.
+    ///
+    /// # Returns
+    /// The nodes, still linked together, or `None` if the list was empty.
+    fn take_nodes(&mut self) -> Option<Chain<T>> {
+        let head = self.head.take()?;
+        let tail = self.tail.take().expect("a list with a head has a tail");
+        Some(Chain {
+            head,
+            tail,
+            len: mem::replace(&mut self.len, 0),
+        })
+    }
+
+    fn allocate(element: T, next: Link<T>) -> NonNull<Node<T>> {
+        let node = Box::new(Node { element, next });
+        // SAFETY: a box is never null
+        unsafe { NonNull::new_unchecked(Box::into_raw(node)) }
+    }
+}
+
+/// Sort the `len` nodes starting at `head`
+fn merge_sort<T, F>(head: Link<T>, len: usize, compare: &mut F) -> Link<T>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    if len < 2 {
+        return head;
+    }
+
+    let middle = len / 2;
+    let mut last = head.expect("the list is shorter than its length");
+    for _ in 1..middle {
+        last = unsafe { (*last.as_ptr()).next }.expect("the list is shorter than its length");
+    }
+    let right = unsafe { (*last.as_ptr()).next.take() };
+
+    let left = merge_sort(head, middle, compare);
+    let right = merge_sort(right, len - middle, compare);
+    merge(left, right, compare)
+}
+
+/// Merge two sorted chains of nodes. On equal elements, the node of `left` comes
+/// first, which keeps the sort stable.
+fn merge<T, F>(mut left: Link<T>, mut right: Link<T>, compare: &mut F) -> Link<T>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut head = None;
+    let mut tail: Option<NonNull<Node<T>>> = None;
+    while let (Some(l), Some(r)) = (left, right) {
+        let source = if unsafe { compare(&(*r.as_ptr()).element, &(*l.as_ptr()).element) }
+            == Ordering::Less
+        {
+            &mut right
+        } else {
+            &mut left
+        };
+        let node = source.expect("both chains are not empty");
+        *source = unsafe { (*node.as_ptr()).next };
+        match tail {
+            Some(tail) => unsafe { (*tail.as_ptr()).next = Some(node) },
+            None => head = Some(node),
+        }
+        tail = Some(node);
+    }
+
+    let rest = left.or(right);
+    match tail {
+        Some(tail) => unsafe { (*tail.as_ptr()).next = rest },
+        None => head = rest,
+    }
+    head
+}
+
+/// Free the nodes one after the other, from the head of the list
+impl<T> Drop for LinkedList<T> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+impl<T> Default for LinkedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Owning iterator, popping the elements from the head of the list
+pub struct IntoIter<T>(LinkedList<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.pop()
+    }
+}
+
+pub struct Iter<'a, T> {
+    next: Option<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.next.map(|node| {
+            self.next = node.next.map(|next| unsafe { &*next.as_ptr() });
+            &node.element
+        })
+    }
+}
+
+pub struct IterMut<'a, T> {
+    next: Option<&'a mut Node<T>>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        self.next.take().map(|node| {
+            self.next = node.next.map(|next| unsafe { &mut *next.as_ptr() });
+            &mut node.element
+        })
+    }
+}
+
+impl<T> IntoIterator for LinkedList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a LinkedList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut LinkedList<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+/// Add the elements at the end of the list, keeping their order: iterating over
+/// the list gives them back in the order they were given.
+impl<T> Extend<T> for LinkedList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for element in iter {
+            self.push_back(element);
+        }
+    }
+}
+
+impl<'a, T: 'a + Copy> Extend<&'a T> for LinkedList<T> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<T> FromIterator<T> for LinkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T: Clone> Clone for LinkedList<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+/// Format the list as a list of its elements, from its head
+impl<T: fmt::Debug> fmt::Debug for LinkedList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self).finish()
+    }
+}
+
+/// Format the list as its elements from its head, linked by arrows: `[a -> b -> c]`
+impl<T: fmt::Display> fmt::Display for LinkedList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[")?;
+        for (i, element) in self.iter().enumerate() {
+            if i > 0 {
+                write!(f, " -> ")?;
+            }
+            element.fmt(f)?;
+        }
+        write!(f, "]")
+    }
+}
+
+impl<T: PartialEq> PartialEq for LinkedList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other)
+    }
+}
+
+impl<T: Eq> Eq for LinkedList<T> {}
+
+/// Lists are compared lexicographically, from their head
+impl<T: PartialOrd> PartialOrd for LinkedList<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other)
+    }
+}
+
+impl<T: Ord> Ord for LinkedList<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other)
+    }
+}
+
+/// The length of the list is hashed before its elements, so that nested lists with
+/// the same elements overall, such as `[[1], [2]]` and `[[1, 2], []]`, hash apart.
+impl<T: Hash> Hash for LinkedList<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        for element in self {
+            element.hash(state);
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    /// Check that the length and the last node kept by the list match its nodes
+    pub(crate) fn assert_consistent<T>(list: &LinkedList<T>) {
+        let mut len = 0;
+        let mut last = None;
+        let mut link = list.head;
+        while let Some(node) = link {
+            len += 1;
+            last = link;
+            link = unsafe { (*node.as_ptr()).next };
+        }
+        assert_eq!(list.len, len);
+        assert_eq!(list.tail, last);
+    }
+
     #[test]
     fn create() {
         let list = LinkedList::<i32>::new();
@@ -49,7 +521,8 @@ mod tests {
         let mut list = LinkedList::new();
         list.push(1);
         assert!(list.head.is_some());
-        assert_eq!(list.head.unwrap().element, 1);
+        assert_eq!(list.head, list.tail);
+        assert_eq!(list.peek(), Some(&1));
     }
 
     #[test]
@@ -60,6 +533,7 @@ mod tests {
         assert_eq!(list.pop(), Some("2"));
         assert_eq!(list.pop(), Some("1"));
         assert!(list.head.is_none());
+        assert!(list.tail.is_none());
     }
 
     #[test]
@@ -70,4 +544,266 @@ mod tests {
         assert_eq!(list.peek(), Some(&1));
         assert_eq!(list.peek(), Some(&1));
     }
+
+    #[test]
+    fn into_iter() {
+        let mut list = LinkedList::new();
+        list.push(1);
+        list.push(2);
+        let mut iter = list.into_iter();
+        assert_eq!(iter.next(), Some(2));
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn iter() {
+        let mut list = LinkedList::new();
+        assert!(list.iter().next().is_none());
+        list.push(1);
+        list.push(2);
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![&2, &1]);
+        let mut sum = 0;
+        for element in &list {
+            sum += element;
+        }
+        assert_eq!(sum, 3);
+        assert_eq!(list.peek(), Some(&2));
+    }
+
+    #[test]
+    fn iter_mut() {
+        let mut list = LinkedList::new();
+        list.push(1);
+        list.push(2);
+        for element in &mut list {
+            *element *= 10;
+        }
+        list.iter_mut().for_each(|element| *element += 1);
+        assert_eq!(list.pop(), Some(21));
+        assert_eq!(list.pop(), Some(11));
+    }
+
+    #[test]
+    fn collect() {
+        let list: LinkedList<_> = (1..4).collect();
+        assert_eq!(list.peek(), Some(&1));
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn extend() {
+        let mut list = LinkedList::new();
+        list.push(1);
+        list.extend(vec![2, 3]);
+        list.extend(Vec::<i32>::new());
+        list.extend(Some(4));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn drop_long_list() {
+        let mut list = LinkedList::new();
+        for i in 0..1_000_000 {
+            list.push(i);
+        }
+        drop(list);
+    }
+
+    fn list<T: Clone>(elements: &[T]) -> LinkedList<T> {
+        elements.iter().cloned().collect()
+    }
+
+    #[test]
+    fn clone() {
+        let original = list(&["a".to_string(), "b".to_string()]);
+        let mut clone = original.clone();
+        clone.push("c".to_string());
+        assert_eq!(original.iter().collect::<Vec<_>>(), vec!["a", "b"]);
+        assert_eq!(clone.iter().collect::<Vec<_>>(), vec!["c", "a", "b"]);
+    }
+
+    #[test]
+    fn debug() {
+        assert_eq!(format!("{:?}", list(&[1, 2, 3])), "[1, 2, 3]");
+        assert_eq!(format!("{:?}", LinkedList::<i32>::new()), "[]");
+        assert_eq!(format!("{:?}", list(&["a"])), "[\"a\"]");
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(list(&["a", "b", "c"]).to_string(), "[a -> b -> c]");
+        assert_eq!(list(&[1]).to_string(), "[1]");
+        assert_eq!(LinkedList::<i32>::new().to_string(), "[]");
+        // The elements are formatted with the flags given to the list
+        assert_eq!(format!("{:.1}", list(&[1.0, 2.46])), "[1.0 -> 2.5]");
+    }
+
+    #[test]
+    fn default() {
+        let list: LinkedList<i32> = Default::default();
+        assert!(list.peek().is_none());
+    }
+
+    #[test]
+    fn eq() {
+        assert_eq!(list(&[1, 2]), list(&[1, 2]));
+        assert_ne!(list(&[1, 2]), list(&[2, 1]));
+        assert_ne!(list(&[1, 2]), list(&[1, 2, 3]));
+        assert_eq!(LinkedList::<i32>::new(), LinkedList::new());
+        assert_ne!(list(&[f64::NAN]), list(&[f64::NAN]));
+    }
+
+    #[test]
+    fn ord() {
+        assert!(list(&[1, 2]) < list(&[1, 3]));
+        assert!(list(&[1, 2]) < list(&[1, 2, 0]));
+        assert!(LinkedList::new() < list(&[0]));
+        assert_eq!(list(&[2]).cmp(&list(&[1, 5])), Ordering::Greater);
+        assert_eq!(list(&[1, 2]).cmp(&list(&[1, 2])), Ordering::Equal);
+        assert_eq!(list(&[f64::NAN]).partial_cmp(&list(&[1.])), None);
+        assert_eq!(list(&[1.]).partial_cmp(&list(&[2.])), Some(Ordering::Less));
+    }
+
+    #[test]
+    fn hash() {
+        use std::collections::hash_map::DefaultHasher;
+
+        fn hash<T: Hash>(value: &T) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        assert_eq!(hash(&list(&[1, 2])), hash(&list(&[1, 2])));
+        assert_ne!(hash(&list(&[1, 2])), hash(&list(&[2, 1])));
+        let nested = list(&[list(&[1]), list(&[2])]);
+        let other = list(&[list(&[1, 2]), LinkedList::new()]);
+        assert_ne!(hash(&nested), hash(&other));
+
+        let mut set = std::collections::HashSet::new();
+        set.insert(list(&[1, 2]));
+        assert!(set.contains(&list(&[1, 2])));
+        assert!(!set.contains(&list(&[1])));
+    }
+
+    #[test]
+    fn extend_by_reference() {
+        let mut list = list(&[1]);
+        list.extend(&[2, 3]);
+        list.extend(Some(4).as_ref());
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+    }
+
+    fn elements<T: Clone>(list: &LinkedList<T>) -> Vec<T> {
+        list.iter().cloned().collect()
+    }
+
+    #[test]
+    fn len() {
+        let mut list = list(&[1, 2, 3]);
+        assert_eq!(list.len(), 3);
+        assert!(!list.is_empty());
+        assert!(list.contains(&2));
+        assert!(!list.contains(&4));
+
+        list.clear();
+        assert_eq!(list.len(), 0);
+        assert!(list.is_empty());
+        assert!(!list.contains(&2));
+    }
+
+    #[test]
+    fn reverse() {
+        let mut list = list(&[1, 2, 3]);
+        list.reverse();
+        assert_eq!(elements(&list), vec![3, 2, 1]);
+        assert_consistent(&list);
+
+        let mut empty = LinkedList::<i32>::new();
+        empty.reverse();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn append() {
+        let mut first = list(&[1, 2]);
+        let mut second = list(&[3, 4]);
+        first.append(&mut second);
+        assert_eq!(elements(&first), vec![1, 2, 3, 4]);
+        assert!(second.is_empty());
+
+        let mut empty = LinkedList::new();
+        empty.append(&mut first);
+        assert_eq!(empty.len(), 4);
+        empty.append(&mut second);
+        assert_eq!(empty.len(), 4);
+        empty.append(&mut list(&[5]));
+        assert_eq!(elements(&empty), vec![1, 2, 3, 4, 5]);
+        assert_consistent(&empty);
+        assert_consistent(&second);
+    }
+
+    #[test]
+    fn split_off() {
+        let mut list = list(&[1, 2, 3, 4]);
+        let tail = list.split_off(1);
+        assert_eq!(elements(&list), vec![1]);
+        assert_eq!(elements(&tail), vec![2, 3, 4]);
+        assert_consistent(&list);
+        assert_consistent(&tail);
+
+        assert!(list.split_off(1).is_empty());
+        let all = list.split_off(0);
+        assert!(list.is_empty());
+        assert_eq!(elements(&all), vec![1]);
+        assert_consistent(&list);
+        assert_consistent(&all);
+    }
+
+    #[test]
+    #[should_panic]
+    fn split_off_out_of_bounds() {
+        list(&[1, 2]).split_off(3);
+    }
+
+    #[test]
+    fn retain() {
+        let mut list: LinkedList<_> = (1..=10).collect();
+        list.retain(|element| element % 3 != 0);
+        assert_eq!(elements(&list), vec![1, 2, 4, 5, 7, 8, 10]);
+        list.retain(|&element| element > 4);
+        assert_eq!(elements(&list), vec![5, 7, 8, 10]);
+        list.retain(|&element| element != 10);
+        assert_eq!(elements(&list), vec![5, 7, 8]);
+        assert_consistent(&list);
+        list.retain(|_| false);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn sort() {
+        let mut list = list(&[5, 1, 4, 2, 3, 2]);
+        list.sort();
+        assert_eq!(elements(&list), vec![1, 2, 2, 3, 4, 5]);
+
+        let mut empty = LinkedList::<i32>::new();
+        empty.sort();
+        assert!(empty.is_empty());
+
+        let mut long: LinkedList<_> = (0..1000).map(|i| (i * 7919) % 1000).collect();
+        long.sort_by(|a, b| b.cmp(a));
+        assert_eq!(elements(&long), (0..1000).rev().collect::<Vec<_>>());
+        assert_consistent(&long);
+    }
+
+    #[test]
+    fn sort_is_stable() {
+        let mut list = list(&[(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd'), (0, 'e')]);
+        list.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            elements(&list),
+            vec![(0, 'e'), (1, 'b'), (1, 'd'), (2, 'a'), (2, 'c')]
+        );
+    }
 }
@@ -0,0 +1,241 @@
+//! Immutable lists, whose nodes are shared between the lists built from one another.
+//!
+//! `prepend` and `tail` are O(1) and leave the original list untouched, which makes
+//! these lists cheap to keep around, for example to undo changes. `rc::List` can
+//! only be used from one thread, `sync::List` can be shared between threads.
+
+macro_rules! persistent_list {
+    ($module:ident, $($pointer:ident)::+) => {
+        pub mod $module {
+            use $($pointer)::+ as Pointer;
+
+            struct Node<T> {
+                element: T,
+                next: Link<T>,
+            }
+
+            type Link<T> = Option<Pointer<Node<T>>>;
+
+            pub struct List<T> {
+                head: Link<T>,
+            }
+
+            impl<T> List<T> {
+                pub fn new() -> Self {
+                    Self { head: None }
+                }
+
+                pub fn is_empty(&self) -> bool {
+                    self.head.is_none()
+                }
+
+                /// Get a new list made of `element` followed by this list, in O(1)
+                pub fn prepend(&self, element: T) -> Self {
+                    Self {
+                        head: Some(Pointer::new(Node {
+                            element,
+                            next: self.head.clone(),
+                        })),
+                    }
+                }
+
+                /// Get a new list made of this list without its head, in O(1).
+                /// The tail of an empty list is empty.
+                pub fn tail(&self) -> Self {
+                    Self {
+                        head: self.head.as_ref().and_then(|node| node.next.clone()),
+                    }
+                }
+
+                /// Get the first element of the list
+                pub fn head(&self) -> Option<&T> {
+                    self.head.as_ref().map(|node| &node.element)
+                }
+
+                /// Iterate over references to the elements, from the head of the list
+                pub fn iter(&self) -> Iter<'_, T> {
+                    Iter {
+                        next: self.head.as_deref(),
+                    }
+                }
+            }
+
+            /// Only the nodes no other list shares are dropped, one after the other:
+            /// the default drop would recurse once per node.
+            ///
+            /// `into_inner` only gives the node back to the last owner, even when
+            /// several threads drop the same node at once. With `try_unwrap`, they
+            /// could all fail and leave the last reference to be dropped recursively.
+            impl<T> Drop for List<T> {
+                fn drop(&mut self) {
+                    let mut link = self.head.take();
+                    while let Some(mut node) = link.and_then(Pointer::into_inner) {
+                        link = node.next.take();
+                    }
+                }
+            }
+
+            /// Cloning a list is O(1): the clone shares every node of the list
+            impl<T> Clone for List<T> {
+                fn clone(&self) -> Self {
+                    Self {
+                        head: self.head.clone(),
+                    }
+                }
+            }
+
+            impl<T> Default for List<T> {
+                fn default() -> Self {
+                    Self::new()
+                }
+            }
+
+            pub struct Iter<'a, T> {
+                next: Option<&'a Node<T>>,
+            }
+
+            impl<'a, T> Iterator for Iter<'a, T> {
+                type Item = &'a T;
+
+                fn next(&mut self) -> Option<&'a T> {
+                    self.next.map(|node| {
+                        self.next = node.next.as_deref();
+                        &node.element
+                    })
+                }
+            }
+
+            impl<'a, T> IntoIterator for &'a List<T> {
+                type Item = &'a T;
+                type IntoIter = Iter<'a, T>;
+
+                fn into_iter(self) -> Iter<'a, T> {
+                    self.iter()
+                }
+            }
+
+            #[cfg(test)]
+            mod tests {
+                use super::*;
+
+                #[test]
+                fn prepend_and_tail() {
+                    let list = List::new();
+                    assert!(list.is_empty());
+                    assert_eq!(list.head(), None);
+                    assert!(list.tail().is_empty());
+
+                    let list = list.prepend(1).prepend(2).prepend(3);
+                    assert_eq!(list.head(), Some(&3));
+                    let tail = list.tail();
+                    assert_eq!(tail.head(), Some(&2));
+                    assert_eq!(tail.tail().head(), Some(&1));
+                    assert!(tail.tail().tail().is_empty());
+
+                    // The original list is left untouched
+                    assert_eq!(list.head(), Some(&3));
+                }
+
+                #[test]
+                fn sharing() {
+                    let base = List::new().prepend(1).prepend(2);
+                    let left = base.prepend(3);
+                    let right = base.prepend(4);
+                    assert_eq!(left.iter().collect::<Vec<_>>(), vec![&3, &2, &1]);
+                    assert_eq!(right.iter().collect::<Vec<_>>(), vec![&4, &2, &1]);
+
+                    let node = base.head.as_ref().unwrap();
+                    assert_eq!(Pointer::strong_count(node), 3);
+                    drop(left);
+                    drop(right);
+                    assert_eq!(Pointer::strong_count(base.head.as_ref().unwrap()), 1);
+                }
+
+                #[test]
+                fn iter() {
+                    let list = List::new().prepend(1).prepend(2);
+                    let mut sum = 0;
+                    for element in &list {
+                        sum += element;
+                    }
+                    assert_eq!(sum, 3);
+                    assert_eq!(list.clone().iter().count(), 2);
+                }
+
+                #[test]
+                fn drop_shared_list() {
+                    let base = List::new().prepend("a").prepend("b");
+                    let list = base.prepend("c");
+                    drop(base);
+                    assert_eq!(list.iter().collect::<Vec<_>>(), vec![&"c", &"b", &"a"]);
+                    drop(list.tail());
+                    assert_eq!(list.iter().count(), 3);
+                }
+
+                #[test]
+                fn drop_long_list() {
+                    let mut list = List::new();
+                    for i in 0..1_000_000 {
+                        list = list.prepend(i);
+                    }
+                    let kept = list.tail();
+                    drop(list);
+                    assert_eq!(kept.head(), Some(&999_998));
+                    drop(kept);
+                }
+            }
+        }
+    };
+}
+
+persistent_list!(rc, std::rc::Rc);
+persistent_list!(sync, std::sync::Arc);
+
+#[cfg(test)]
+mod tests {
+    use super::sync::List;
+    use std::thread;
+
+    #[test]
+    fn share_between_threads() {
+        let base = List::new().prepend(1).prepend(2);
+
+        let handles: Vec<_> = (10..14)
+            .map(|i| {
+                let base = base.clone();
+                thread::spawn(move || {
+                    let list = base.prepend(i);
+                    list.iter().sum::<i32>()
+                })
+            })
+            .collect();
+        let sums: Vec<_> = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect();
+
+        assert_eq!(sums, vec![13, 14, 15, 16]);
+        assert_eq!(base.iter().collect::<Vec<_>>(), vec![&2, &1]);
+    }
+
+    #[test]
+    fn drop_long_list_from_threads() {
+        let mut list = List::new();
+        for i in 0..1_000_000 {
+            list = list.prepend(i);
+        }
+
+        // Whichever thread drops the last reference drops the whole list, without
+        // overflowing its stack
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let list = list.prepend(i);
+                thread::spawn(move || drop(list))
+            })
+            .collect();
+        drop(list);
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//! Serialize a `LinkedList` as a sequence of its elements, from its head, so that it
+//! reads like a `Vec` in any format.
+
+use super::LinkedList;
+use serde::{
+    de::{SeqAccess, Visitor},
+    ser::SerializeSeq,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::{fmt, marker::PhantomData};
+
+impl<T: Serialize> Serialize for LinkedList<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // Some formats need the length before the elements
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for element in self {
+            seq.serialize_element(element)?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for LinkedList<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(ListVisitor(PhantomData))
+    }
+}
+
+struct ListVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for ListVisitor<T> {
+    type Value = LinkedList<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a sequence")
+    }
+
+    /// Link every element at the end of the list as it is read, in order
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut list = LinkedList::new();
+        while let Some(element) = seq.next_element()? {
+            list.push_back(element);
+        }
+        Ok(list)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<T>(list: &LinkedList<T>) -> LinkedList<T>
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+    {
+        serde_json::from_str(&serde_json::to_string(list).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn serialize_in_order() {
+        let list: LinkedList<_> = (1..4).collect();
+        assert_eq!(serde_json::to_string(&list).unwrap(), "[1,2,3]");
+        let empty = LinkedList::<i32>::new();
+        assert_eq!(serde_json::to_string(&empty).unwrap(), "[]");
+    }
+
+    #[test]
+    fn deserialize_in_order() {
+        let list: LinkedList<String> = serde_json::from_str(r#"["a", "b"]"#).unwrap();
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec!["a", "b"]);
+        assert!(serde_json::from_str::<LinkedList<i32>>("{}").is_err());
+        assert!(serde_json::from_str::<LinkedList<i32>>(r#"[1, "2"]"#).is_err());
+    }
+
+    #[test]
+    fn round_trips() {
+        let list: LinkedList<_> = vec!["a".to_string(), "b".to_string()].into_iter().collect();
+        assert_eq!(round_trip(&list), list);
+        assert_eq!(round_trip(&LinkedList::<u8>::new()), LinkedList::new());
+
+        let nested: LinkedList<LinkedList<_>> = vec![(0..2).collect(), LinkedList::new()]
+            .into_iter()
+            .collect();
+        assert_eq!(serde_json::to_string(&nested).unwrap(), "[[0,1],[]]");
+        assert_eq!(round_trip(&nested), nested);
+    }
+
+    #[test]
+    fn round_trip_long_list() {
+        let list: LinkedList<_> = (0..100_000).collect();
+        assert_eq!(round_trip(&list), list);
+    }
+}
@@ -6,6 +6,30 @@ pub struct Bank {
     accounts: HashMap<String, Money>,
 }
 
+/// Reasons for an operation on the bank to fail
+#[derive(Debug, PartialEq)]
+pub enum BankError {
+    /// The account does not exist
+    NoSuchAccount(String),
+    /// The account would hold more money than a `Money` can represent
+    Overflow(String),
+}
+
+use std::fmt;
+
+impl fmt::Display for BankError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BankError::NoSuchAccount(account_name) => {
+                write!(f, "no account named {}", account_name)
+            }
+            BankError::Overflow(account_name) => {
+                write!(f, "{} cannot hold that much money", account_name)
+            }
+        }
+    }
+}
+
 impl Bank {
     pub fn new() -> Bank {
         Bank {
@@ -24,19 +48,22 @@ impl Bank {
         }
     }
 
-    /// Return true if the account was updated successfully, false if it didn't
-    /// exist already
-    pub fn add_money(&mut self, account_name: &str, money_amount: Money) -> bool {
+    /// Add money to an account. The account is left untouched if it cannot hold
+    /// that much money.
+    pub fn add_money(&mut self, account_name: &str, money_amount: Money) -> Result<(), BankError> {
         match self.accounts.get_mut(account_name) {
-            None => false,
+            None => Err(BankError::NoSuchAccount(account_name.to_string())),
             Some(amount) => {
-                *amount += money_amount;
-                true
+                *amount = amount
+                    .checked_add(money_amount)
+                    .ok_or_else(|| BankError::Overflow(account_name.to_string()))?;
+                Ok(())
             }
         }
     }
 
-    fn mean(&self) -> Money {
+    /// Return the mean amount of money of the accounts, or None if there is no account
+    fn mean(&self) -> Option<Money> {
         // // Sequential way
         // let mut sum = 0;
 
@@ -46,22 +73,70 @@ impl Bank {
 
         // sum / self.accounts.len()
 
-        self.accounts
-            .iter()
-            .fold(0, |sum, (_, current_amount)| sum + current_amount)
-            / self.accounts.len() as u64
+        if self.accounts.is_empty() {
+            return None;
+        }
+
+        // The sum of u64s overflows quickly, but it would take 2^64 accounts to
+        // overflow a u128. The mean itself is never bigger than the richest account.
+        let sum = self
+            .accounts
+            .values()
+            .fold(0u128, |sum, current_amount| sum + *current_amount as u128);
+        Some((sum / self.accounts.len() as u128) as Money)
     }
 }
 
-use std::fmt;
+impl Default for Bank {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl fmt::Display for Bank {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "mean monies: {}, account holders:", self.mean())?;
-        for (account_name, _) in &self.accounts {
+        match self.mean() {
+            None => write!(f, "no account holders")?,
+            Some(mean) => write!(f, "mean monies: {}, account holders:", mean)?,
+        }
+        for account_name in self.accounts.keys() {
             write!(f, "\n{}", account_name)?;
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_money() {
+        let mut bank = Bank::new();
+        bank.add_account("Trisha Paytas");
+
+        assert_eq!(bank.add_money("Trisha Paytas", 10), Ok(()));
+        assert_eq!(
+            bank.add_money("Joe Biden", 10),
+            Err(BankError::NoSuchAccount("Joe Biden".to_string()))
+        );
+        assert_eq!(
+            bank.add_money("Trisha Paytas", Money::MAX),
+            Err(BankError::Overflow("Trisha Paytas".to_string()))
+        );
+        assert_eq!(bank.accounts["Trisha Paytas"], 10);
+    }
+
+    #[test]
+    fn mean() {
+        let mut bank = Bank::new();
+        assert_eq!(bank.mean(), None);
+
+        bank.add_account("Trisha Paytas");
+        bank.add_account("Jeffree Star");
+        bank.add_money("Trisha Paytas", Money::MAX).unwrap();
+        bank.add_money("Jeffree Star", Money::MAX - 2).unwrap();
+        assert_eq!(bank.mean(), Some(Money::MAX - 1));
+    }
+}
@@ -4,9 +4,13 @@ use bank::Bank;
 fn main() {
     let mut bank_of_america = Bank::new();
     bank_of_america.add_account("Trisha Paytas");
-    bank_of_america.add_money("Trisha Paytas", 150000);
+    if let Err(error) = bank_of_america.add_money("Trisha Paytas", 150000) {
+        println!("{}", error);
+    }
 
-    bank_of_america.add_money("Joe Biden", 99999999999999);
+    if let Err(error) = bank_of_america.add_money("Joe Biden", 99999999999999) {
+        println!("{}", error);
+    }
 
     println!("Bank of America: {}", bank_of_america);
 }
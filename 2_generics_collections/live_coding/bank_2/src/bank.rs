@@ -1,10 +1,51 @@
-use crate::money::Money;
+use crate::money::{self, Money, MoneyError};
 use std::collections::HashMap;
 
 pub struct Bank<T: Money> {
     accounts: HashMap<String, T>,
 }
 
+/// Reasons for an operation on the bank to fail
+#[derive(Debug, PartialEq)]
+pub enum BankError {
+    /// The account does not exist
+    NoSuchAccount(String),
+    /// The account would hold more money than its currency can represent
+    Overflow(String),
+    /// The account does not hold enough money
+    InsufficientFunds(String),
+    /// Money cannot be distributed when all the ratios are 0
+    NoRatio,
+    /// The amount cannot be represented in its currency
+    InvalidAmount(MoneyError),
+}
+
+impl From<MoneyError> for BankError {
+    fn from(err: MoneyError) -> Self {
+        BankError::InvalidAmount(err)
+    }
+}
+
+use std::fmt;
+
+impl fmt::Display for BankError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BankError::NoSuchAccount(account_name) => {
+                write!(f, "no account named {}", account_name)
+            }
+            BankError::Overflow(account_name) => {
+                write!(f, "{} cannot hold that much money", account_name)
+            }
+            BankError::InsufficientFunds(account_name) => {
+                write!(f, "{} does not have enough money", account_name)
+            }
+            BankError::NoRatio => write!(f, "money cannot be distributed without ratios"),
+            BankError::InvalidAmount(err) => err.fmt(f),
+        }
+    }
+}
+
 impl<T: Money> Bank<T> {
     // or impl<T> Bank<T> where T: Money
     pub fn new() -> Bank<T> {
@@ -17,27 +58,84 @@ impl<T: Money> Bank<T> {
     pub fn add_account(&mut self, account_name: &str) -> bool {
         match self.accounts.get(account_name) {
             None => {
-                self.accounts
-                    .insert(String::from(account_name), T::from(0f64));
+                self.accounts.insert(String::from(account_name), T::zero());
                 true
             }
             Some(_) => false,
         }
     }
 
-    /// Return true if the account was updated successfully, false if it didn't
-    /// exist already
-    pub fn add_money(&mut self, account_name: &str, money_amount: T) -> bool {
-        match self.accounts.get_mut(account_name) {
-            None => false,
-            Some(amount) => {
-                *amount = T::from(amount.dollar_value() + money_amount.dollar_value());
-                true
+    /// Add money to an account. The account is left untouched if it cannot hold
+    /// that much money.
+    pub fn add_money(&mut self, account_name: &str, money_amount: T) -> Result<(), BankError> {
+        let amount = self.account_mut(account_name)?;
+        *amount = amount
+            .checked_add(money_amount)
+            .ok_or_else(|| BankError::Overflow(account_name.to_string()))?;
+        Ok(())
+    }
+
+    /// Take money from an account, which cannot go below zero
+    pub fn retrieve_money(&mut self, account_name: &str, money_amount: T) -> Result<(), BankError> {
+        let amount = self.account_mut(account_name)?;
+        // Unsigned money cannot go below zero, but signed money such as `Dollar` can
+        let remaining = match amount.checked_sub(money_amount) {
+            Some(remaining) if remaining >= T::zero() => remaining,
+            // Only taking away a negative amount can go past the largest amount
+            None if money_amount < T::zero() => {
+                return Err(BankError::Overflow(account_name.to_string()))
             }
+            _ => return Err(BankError::InsufficientFunds(account_name.to_string())),
+        };
+        *amount = remaining;
+        Ok(())
+    }
+
+    /// Move all the money of an account into other accounts, in proportion to
+    /// their ratio. No money is lost or created: the shares always add up to what
+    /// was in `from`, as computed by `Money::allocate`.
+    ///
+    /// Nothing is changed when an error is returned.
+    pub fn distribute(&mut self, from: &str, shares: &[(&str, u64)]) -> Result<(), BankError> {
+        let balance = *self.account(from)?;
+        let ratios: Vec<u64> = shares.iter().map(|(_, ratio)| *ratio).collect();
+        let parts = balance.allocate(&ratios).ok_or(BankError::NoRatio)?;
+
+        // Compute every new balance before changing any, so that a failure leaves
+        // the bank untouched
+        let mut updated: HashMap<&str, T> = HashMap::new();
+        updated.insert(from, T::zero());
+        for ((to, _), part) in shares.iter().zip(parts) {
+            let current = match updated.get(to) {
+                Some(current) => *current,
+                None => *self.account(to)?,
+            };
+            let sum = current
+                .checked_add(part)
+                .ok_or_else(|| BankError::Overflow(to.to_string()))?;
+            updated.insert(to, sum);
+        }
+
+        for (name, amount) in updated {
+            self.accounts.insert(name.to_string(), amount);
         }
+        Ok(())
+    }
+
+    fn account(&self, account_name: &str) -> Result<&T, BankError> {
+        self.accounts
+            .get(account_name)
+            .ok_or_else(|| BankError::NoSuchAccount(account_name.to_string()))
+    }
+
+    fn account_mut(&mut self, account_name: &str) -> Result<&mut T, BankError> {
+        self.accounts
+            .get_mut(account_name)
+            .ok_or_else(|| BankError::NoSuchAccount(account_name.to_string()))
     }
 
-    fn mean(&self) -> f64 {
+    /// Return the mean amount of money of the accounts, or None if there is no account
+    fn mean(&self) -> Option<T> {
         // // Sequential way
         // let mut sum = 0;
 
@@ -47,20 +145,25 @@ impl<T: Money> Bank<T> {
 
         // sum / self.accounts.len()
 
-        self.accounts.iter().fold(0f64, |sum, (_, current_amount)| {
-            sum + current_amount.dollar_value()
-        }) / self.accounts.len() as f64
+        money::mean(self.accounts.values().copied())
     }
 }
 
-use std::fmt;
+impl<T: Money> Default for Bank<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl<T> fmt::Display for Bank<T>
 where
     T: Money,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "mean monies: {}$, account holders:", self.mean())?;
+        match self.mean() {
+            None => write!(f, "no account holders")?,
+            Some(mean) => write!(f, "mean monies: {}, account holders:", mean)?,
+        }
         for (account_name, money) in &self.accounts {
             write!(f, "\n{}: {}", account_name, money)?;
         }
@@ -68,3 +171,122 @@ where
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::{Dollar, Euro};
+
+    fn bank(accounts: &[(&str, u64)]) -> Bank<Euro> {
+        let mut bank = Bank::new();
+        for (name, minor) in accounts {
+            bank.add_account(name);
+            bank.add_money(name, Euro::from_minor(*minor)).unwrap();
+        }
+        bank
+    }
+
+    #[test]
+    fn distribute() {
+        let mut bank = bank(&[("bill", 1000), ("alice", 0), ("bob", 50)]);
+
+        assert_eq!(bank.distribute("bill", &[("alice", 1), ("bob", 2)]), Ok(()));
+        assert_eq!(bank.accounts["bill"], Euro::from_minor(0));
+        assert_eq!(bank.accounts["alice"], Euro::from_minor(333));
+        assert_eq!(bank.accounts["bob"], Euro::from_minor(717));
+    }
+
+    #[test]
+    fn distribute_to_itself() {
+        let mut bank = bank(&[("interest", 10), ("alice", 0)]);
+
+        assert_eq!(
+            bank.distribute(
+                "interest",
+                &[("interest", 1), ("alice", 1), ("interest", 1)]
+            ),
+            Ok(())
+        );
+        assert_eq!(bank.accounts["interest"], Euro::from_minor(7));
+        assert_eq!(bank.accounts["alice"], Euro::from_minor(3));
+    }
+
+    #[test]
+    fn distribute_fails_without_change() {
+        let mut bank = bank(&[("bill", 1000), ("alice", 0), ("full", u64::MAX)]);
+
+        let no_such_account = |name: &str| Err(BankError::NoSuchAccount(name.to_string()));
+        assert_eq!(
+            bank.distribute("nobody", &[("alice", 1)]),
+            no_such_account("nobody")
+        );
+        assert_eq!(
+            bank.distribute("bill", &[("alice", 1), ("nobody", 1)]),
+            no_such_account("nobody")
+        );
+        assert_eq!(
+            bank.distribute("bill", &[("alice", 0)]),
+            Err(BankError::NoRatio)
+        );
+        assert_eq!(
+            bank.distribute("bill", &[("alice", 1), ("full", 1)]),
+            Err(BankError::Overflow("full".to_string()))
+        );
+        assert_eq!(bank.accounts["bill"], Euro::from_minor(1000));
+        assert_eq!(bank.accounts["alice"], Euro::from_minor(0));
+    }
+
+    #[test]
+    fn add_and_retrieve_money() {
+        let mut bank = bank(&[("alice", 100)]);
+
+        assert_eq!(
+            bank.add_money("alice", Euro::from_minor(u64::MAX)),
+            Err(BankError::Overflow("alice".to_string()))
+        );
+        assert_eq!(
+            bank.retrieve_money("alice", Euro::from_minor(101)),
+            Err(BankError::InsufficientFunds("alice".to_string()))
+        );
+        assert_eq!(
+            bank.add_money("bob", Euro::new(1).unwrap()),
+            Err(BankError::NoSuchAccount("bob".to_string()))
+        );
+        assert_eq!(bank.retrieve_money("alice", Euro::from_minor(40)), Ok(()));
+        assert_eq!(bank.accounts["alice"], Euro::from_minor(60));
+    }
+
+    #[test]
+    fn retrieve_signed_money() {
+        let mut bank = Bank::<Dollar>::new();
+        bank.add_account("a");
+
+        assert_eq!(
+            bank.retrieve_money("a", Dollar::new(5.).unwrap()),
+            Err(BankError::InsufficientFunds("a".to_string()))
+        );
+        assert_eq!(bank.accounts["a"], Dollar::zero());
+
+        bank.add_money("a", Dollar::from_minor(i128::MAX)).unwrap();
+        assert_eq!(
+            bank.retrieve_money("a", Dollar::from_minor(-1)),
+            Err(BankError::Overflow("a".to_string()))
+        );
+        assert_eq!(
+            bank.retrieve_money("a", Dollar::from_minor(i128::MAX)),
+            Ok(())
+        );
+        assert_eq!(bank.accounts["a"], Dollar::zero());
+    }
+
+    #[test]
+    fn mean() {
+        assert_eq!(Bank::<Euro>::new().mean(), None);
+
+        let bank = bank(&[("alice", u64::MAX), ("bob", u64::MAX - 2), ("carol", 1)]);
+        assert_eq!(
+            bank.mean(),
+            Some(Euro::from_minor(((u64::MAX as u128 * 2 - 1) / 3) as u64))
+        );
+    }
+}
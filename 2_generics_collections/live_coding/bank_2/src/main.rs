@@ -1,24 +1,63 @@
 mod bank;
 mod money;
+mod multi_bank;
 
-use bank::Bank;
-use money::{Dollar, Euro, Ouguiya};
+use bank::{Bank, BankError};
+use money::{Dollar, Euro, Money, Ouguiya};
+use multi_bank::MultiBank;
 
-fn main() {
+fn main() -> Result<(), BankError> {
     let mut bank_of_america = Bank::new();
     bank_of_america.add_account("Trisha Paytas");
-    bank_of_america.add_money("Trisha Paytas", Dollar::new(16000.0));
-    bank_of_america.add_money("Joe Biden", Dollar::new(99999999999999.0));
+    bank_of_america.add_money("Trisha Paytas", Dollar::new(16000.0)?)?;
+    if let Err(error) = bank_of_america.add_money("Joe Biden", Dollar::new(99999999999999.0)?) {
+        println!("{}", error);
+    }
+    bank_of_america.add_account("Jeffree Star");
+    bank_of_america.add_account("Lottery");
+    bank_of_america.add_money("Lottery", Dollar::new(1000.0)?)?;
+    bank_of_america.distribute("Lottery", &[("Trisha Paytas", 1), ("Jeffree Star", 2)])?;
 
     let mut bnp = Bank::new();
     bnp.add_account("Jojo");
-    bnp.add_money("Jojo", Euro::new(1600));
+    bnp.add_money("Jojo", Euro::new(1700)?)?;
+    bnp.retrieve_money("Jojo", Euro::new(100)?)?;
 
     let mut maybank = Bank::new();
     maybank.add_account("Jimmy Choo");
-    maybank.add_money("Jimmy Choo", Ouguiya::new(u64::MAX));
+    if let Err(error) = Ouguiya::new(u64::MAX) {
+        println!("{}", error);
+    }
+    maybank.add_money("Jimmy Choo", Ouguiya::from_minor(u64::MAX))?;
+    if let Err(error) = maybank.add_money("Jimmy Choo", Ouguiya::new(1)?) {
+        println!("{}", error);
+    }
 
     println!("Bank of America: {}", bank_of_america);
     println!("BNP Paribas: {}", bnp);
     println!("Maybank: {}", maybank);
+
+    // A single bank can also hold accounts in every currency
+    let mut hsbc = MultiBank::new();
+    hsbc.add_account::<Dollar>("Trisha Paytas");
+    hsbc.add_account::<Euro>("Jojo");
+    hsbc.add_account::<Ouguiya>("Jimmy Choo");
+    hsbc.add_money("Trisha Paytas", Euro::new(100)?)?;
+    hsbc.add_money("Jojo", Euro::new(1600)?)?;
+    hsbc.add_money("Jimmy Choo", Dollar::new(300.0)?)?;
+    println!("HSBC: {}", hsbc);
+
+    // Currencies cannot be mixed, they have to be converted first
+    let rent = Euro::new(800)? * 12;
+    println!("Yearly rent: {} or {}", rent, rent.convert_to::<Dollar>()?);
+    if Ouguiya::from_minor(u64::MAX).checked_mul(2).is_none() {
+        println!("Jimmy Choo cannot double his money");
+    }
+
+    // Splitting a bill between friends does not lose a single cent
+    let shares = Euro::new(100)?.split(3).unwrap();
+    let shares: Vec<String> = shares.iter().map(|share| share.to_string()).collect();
+    println!("Everyone pays {}", shares.join(", "));
+
+    Ok(())
 }
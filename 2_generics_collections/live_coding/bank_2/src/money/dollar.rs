@@ -1,33 +1,58 @@
+use super::{Locale, Money};
+use std::convert::TryFrom;
+use std::ops::Neg;
+
+// Dollars are counted in cents, which can be negative
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Dollar {
-    amount: f64,
+    cents: i128,
 }
 
 impl Dollar {
-    pub fn new(amount: f64) -> Dollar {
-        Dollar { amount }
+    pub fn new(amount: f64) -> Result<Dollar, super::MoneyError> {
+        Dollar::try_from(amount)
+    }
+
+    pub fn from_minor(cents: i128) -> Dollar {
+        Dollar { cents }
+    }
+
+    // Negate this amount, or return None if the result overflows
+    pub fn checked_neg(self) -> Option<Dollar> {
+        self.cents.checked_neg().map(Dollar::from_minor)
     }
 }
 
-impl super::Money for Dollar {
+impl Money for Dollar {
     fn exchange_rate() -> f64 {
         1f64 // or 1.0
     }
 
-    fn amount(&self) -> f64 {
-        self.amount
+    fn symbol() -> &'static str {
+        "$"
     }
-}
 
-impl From<f64> for Dollar {
-    fn from(amount: f64) -> Self {
-        Dollar { amount }
+    fn locale() -> Locale {
+        Locale::en_us()
+    }
+
+    fn try_from_minor(cents: i128) -> Option<Self> {
+        Some(Dollar { cents })
+    }
+
+    fn minor(&self) -> i128 {
+        self.cents
     }
 }
 
-use std::fmt;
+money_operators!(Dollar);
+
+// Unlike euros and ouguiyas, dollars can be negative
+impl Neg for Dollar {
+    type Output = Dollar;
 
-impl fmt::Display for Dollar {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}$", self.amount)
+    fn neg(self) -> Dollar {
+        self.checked_neg()
+            .expect("attempt to negate money with overflow")
     }
 }
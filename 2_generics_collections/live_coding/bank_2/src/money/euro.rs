@@ -1,48 +1,42 @@
-use super::Money;
+use super::{Locale, Money};
+use std::convert::TryFrom;
 
-// Let's make euros a bit more interesting and keep an amount of cents and an amount of
-// integers
+// Euros are counted in cents, so that adding them up never loses any
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Euro {
-    integer: u64,
-    decimal: u64,
+    cents: u64,
 }
 
 impl Euro {
-    pub fn new(amount: u64) -> Euro {
-        Euro {
-            integer: amount,
-            decimal: 0,
-        }
+    pub fn new(amount: u64) -> Result<Euro, super::MoneyError> {
+        super::from_units(amount)
     }
-}
 
-impl Money for Euro {
-    fn amount(&self) -> f64 {
-        self.integer as f64 + self.decimal as f64 / 100f64
+    pub fn from_minor(cents: u64) -> Euro {
+        Euro { cents }
     }
+}
 
+impl Money for Euro {
     fn exchange_rate() -> f64 {
         1.17
     }
-}
 
-impl From<f64> for Euro {
-    fn from(amount: f64) -> Self {
-        let amount = amount / Euro::exchange_rate();
-        let integer_amount = amount as u64;
-        let decimal_amount = amount - integer_amount as f64;
+    fn symbol() -> &'static str {
+        "€"
+    }
 
-        Euro {
-            integer: integer_amount,
-            decimal: decimal_amount as u64,
-        }
+    fn locale() -> Locale {
+        Locale::fr_fr()
     }
-}
 
-use std::fmt;
+    fn try_from_minor(cents: i128) -> Option<Self> {
+        u64::try_from(cents).ok().map(Euro::from_minor)
+    }
 
-impl fmt::Display for Euro {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}.{} euros", self.integer, self.decimal)
+    fn minor(&self) -> i128 {
+        self.cents as i128
     }
 }
+
+money_operators!(Euro);
@@ -0,0 +1,90 @@
+// Where the symbol of a currency is written
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymbolPosition {
+    // `$1.00`
+    Before,
+    // `1,00 €`
+    After,
+}
+
+// Conventions used to write amounts of money
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Locale {
+    pub decimal_separator: char,
+    // Written between groups of three digits
+    pub grouping_separator: char,
+    pub symbol_position: SymbolPosition,
+    // Whether a space is written between the symbol and the number
+    pub symbol_spaced: bool,
+}
+
+impl Locale {
+    // `$1,234.56`, `-$1,234.56`
+    pub fn en_us() -> Locale {
+        Locale {
+            decimal_separator: '.',
+            grouping_separator: ',',
+            symbol_position: SymbolPosition::Before,
+            symbol_spaced: false,
+        }
+    }
+
+    // `1 234,56 €`, `-1 234,56 €`
+    pub fn fr_fr() -> Locale {
+        Locale {
+            decimal_separator: ',',
+            grouping_separator: ' ',
+            symbol_position: SymbolPosition::After,
+            symbol_spaced: true,
+        }
+    }
+
+    // Write an amount of money counted in minor units, with `digits` digits after the
+    // decimal separator
+    pub fn format_minor(&self, minor: i128, digits: u32, symbol: &str) -> String {
+        let scale = 10u128.pow(digits);
+        let magnitude = minor.unsigned_abs();
+
+        let mut number = String::new();
+        let units = (magnitude / scale).to_string();
+        for (i, digit) in units.chars().enumerate() {
+            if i > 0 && (units.len() - i).is_multiple_of(3) {
+                number.push(self.grouping_separator);
+            }
+            number.push(digit);
+        }
+        if digits > 0 {
+            number.push(self.decimal_separator);
+            number.push_str(&format!(
+                "{:0width$}",
+                magnitude % scale,
+                width = digits as usize
+            ));
+        }
+
+        let space = if self.symbol_spaced { " " } else { "" };
+        let sign = if minor < 0 { "-" } else { "" };
+        match self.symbol_position {
+            SymbolPosition::Before => format!("{}{}{}{}", sign, symbol, space, number),
+            SymbolPosition::After => format!("{}{}{}{}", sign, number, space, symbol),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_minor() {
+        let en = Locale::en_us();
+        assert_eq!(en.format_minor(123450, 2, "$"), "$1,234.50");
+        assert_eq!(en.format_minor(-5, 2, "$"), "-$0.05");
+        assert_eq!(en.format_minor(123456789, 0, "¥"), "¥123,456,789");
+
+        let fr = Locale::fr_fr();
+        assert_eq!(fr.format_minor(160000, 2, "€"), "1 600,00 €");
+        assert_eq!(fr.format_minor(-110, 2, "€"), "-1,10 €");
+        assert_eq!(fr.format_minor(250, 2, "UM"), "2,50 UM");
+    }
+}
@@ -1,21 +1,540 @@
+// Implement the conversion from dollars and the operators of a currency, on top of
+// the checked methods of `Money`. Like the operators of integers, the operators panic
+// when the result cannot be represented, in release builds too: use `checked_add`,
+// `checked_sub` and `checked_mul` to handle it instead.
+macro_rules! money_operators {
+    ($money:ident) => {
+        impl std::convert::TryFrom<f64> for $money {
+            type Error = $crate::money::MoneyError;
+
+            // Convert a dollar value, rounded to the closest minor unit
+            fn try_from(dollars: f64) -> Result<Self, Self::Error> {
+                let minor = $crate::money::minor_from_dollars::<$money>(dollars)?;
+                <$money as $crate::money::Money>::try_from_minor(minor)
+                    .ok_or($crate::money::MoneyError::OutOfRange)
+            }
+        }
+
+        impl std::ops::Add for $money {
+            type Output = $money;
+
+            fn add(self, other: $money) -> $money {
+                $crate::money::Money::checked_add(self, other)
+                    .expect("attempt to add money with overflow")
+            }
+        }
+
+        impl std::ops::Sub for $money {
+            type Output = $money;
+
+            fn sub(self, other: $money) -> $money {
+                $crate::money::Money::checked_sub(self, other)
+                    .expect("attempt to subtract money with overflow")
+            }
+        }
+
+        impl std::ops::Mul<u64> for $money {
+            type Output = $money;
+
+            fn mul(self, factor: u64) -> $money {
+                $crate::money::Money::checked_mul(self, factor)
+                    .expect("attempt to multiply money with overflow")
+            }
+        }
+
+        impl std::iter::Sum for $money {
+            fn sum<I: Iterator<Item = $money>>(iter: I) -> $money {
+                iter.fold(<$money as $crate::money::Money>::zero(), |sum, money| {
+                    sum + money
+                })
+            }
+        }
+
+        impl std::fmt::Display for $money {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                $crate::money::Money::fmt_amount(self, f)
+            }
+        }
+    };
+}
+
 pub mod dollar;
 pub mod euro;
+pub mod format;
 pub mod ouguiya;
 
 pub use dollar::Dollar;
 pub use euro::Euro;
+pub use format::Locale;
 pub use ouguiya::Ouguiya;
 
+use std::convert::TryFrom;
 use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, Mul, Sub};
+
+/// Reasons for an amount not to be representable in a currency
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MoneyError {
+    /// The amount is not a number, or is infinite
+    NotFinite,
+    /// The amount is too big for the currency, or negative when it cannot be
+    OutOfRange,
+}
 
-pub trait Money: fmt::Display + From<f64> {
+impl fmt::Display for MoneyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoneyError::NotFinite => write!(f, "an amount of money must be finite"),
+            MoneyError::OutOfRange => write!(f, "amount of money out of range for its currency"),
+        }
+    }
+}
+
+// Every currency counts its money in minor units (cents for euros and dollars, khoums
+// for ouguiyas), so that amounts are exact and only conversions need to round. Each
+// currency stores them in its own integer type, and the trait handles them as i128,
+// which holds all of them.
+//
+// Arithmetic only works between amounts of the same currency: adding euros to dollars
+// does not compile, they have to be converted first with `convert_to`.
+pub trait Money:
+    fmt::Display
+    + TryFrom<f64, Error = MoneyError>
+    + Copy
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<u64, Output = Self>
+    + Sum
+{
     // Represent the exchange rate between this money and dollars
     fn exchange_rate() -> f64;
 
+    // Symbol written next to amounts of this currency, such as `€`
+    fn symbol() -> &'static str;
+
+    // Conventions used to write amounts of this currency
+    fn locale() -> Locale;
+
+    // Number of digits of the minor unit of this currency
+    fn minor_digits() -> u32 {
+        2
+    }
+
+    // Create an amount of money from a number of minor units, or return None if this
+    // currency cannot represent it
+    fn try_from_minor(minor: i128) -> Option<Self>;
+
+    // Represent the amount of money held in that currency, in minor units
+    fn minor(&self) -> i128;
+
+    fn zero() -> Self {
+        Self::try_from_minor(0).expect("every currency can hold no money")
+    }
+
     // Represent the amount of money held in that currency
-    fn amount(&self) -> f64;
+    fn amount(&self) -> f64 {
+        self.minor() as f64 / minor_per_unit::<Self>() as f64
+    }
+
+    // Add two amounts of money, or return None if the result overflows
+    fn checked_add(self, other: Self) -> Option<Self> {
+        self.minor()
+            .checked_add(other.minor())
+            .and_then(Self::try_from_minor)
+    }
+
+    // Subtract an amount of money from this one, or return None if the result
+    // overflows or is negative when the currency cannot be
+    fn checked_sub(self, other: Self) -> Option<Self> {
+        self.minor()
+            .checked_sub(other.minor())
+            .and_then(Self::try_from_minor)
+    }
+
+    // Multiply by a scalar, or return None if the result overflows
+    fn checked_mul(self, factor: u64) -> Option<Self> {
+        self.minor()
+            .checked_mul(factor as i128)
+            .and_then(Self::try_from_minor)
+    }
+
+    // Split this amount into parts proportional to `ratios`, which always sum up to
+    // this amount. The minor units left over by the division go one by one to the parts
+    // whose share was rounded down the most, the first parts winning ties.
+    // Return None if all the ratios are 0, or if the amount is too big to be split
+    // exactly.
+    fn allocate(self, ratios: &[u64]) -> Option<Vec<Self>> {
+        let total: u128 = ratios.iter().map(|&ratio| ratio as u128).sum();
+        if total == 0 {
+            return None;
+        }
+
+        // A negative amount is split like its opposite, so that its parts are rounded
+        // towards 0 too
+        let negative = self.minor() < 0;
+        let minor = self.minor().unsigned_abs();
+        let mut shares: Vec<u128> = Vec::with_capacity(ratios.len());
+        let mut remainders: Vec<(u128, usize)> = Vec::with_capacity(ratios.len());
+        for (i, &ratio) in ratios.iter().enumerate() {
+            let exact = minor.checked_mul(ratio as u128)?;
+            shares.push(exact / total);
+            remainders.push((exact % total, i));
+        }
+
+        let allocated: u128 = shares.iter().sum();
+        remainders.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        for &(_, i) in remainders.iter().take((minor - allocated) as usize) {
+            shares[i] += 1;
+        }
+
+        let parts = shares.into_iter().map(|share| {
+            // A share is at most the amount, so the only one which does not fit in
+            // an i128 is 2^127, which wraps to i128::MIN and stays so when negated
+            let share = share as i128;
+            let share = if negative {
+                share.wrapping_neg()
+            } else {
+                share
+            };
+            Self::try_from_minor(share).expect("a share is at most the amount")
+        });
+        Some(parts.collect())
+    }
+
+    // Split this amount into `parts` equal parts, the first ones getting the minor
+    // units left over. Return None if `parts` is 0.
+    fn split(self, parts: usize) -> Option<Vec<Self>> {
+        self.allocate(&vec![1; parts])
+    }
 
     fn dollar_value(&self) -> f64 {
         self.amount() * Self::exchange_rate()
     }
+
+    // Convert this amount of money into another currency, through its dollar value
+    fn convert_to<T: Money>(&self) -> Result<T, MoneyError> {
+        T::try_from(self.dollar_value())
+    }
+
+    // Write the amount with the locale of its currency, such as `$12.30` or `12,30 €`
+    fn fmt_amount(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&Self::locale().format_minor(
+            self.minor(),
+            Self::minor_digits(),
+            Self::symbol(),
+        ))
+    }
+}
+
+/// Compute the mean of amounts of money, rounded down, or return None if there are
+/// none.
+///
+/// # Arguments
+/// * `amounts` - The amounts of money, of the same currency
+///
+/// # Returns
+/// The mean, which never overflows: each amount is divided before being summed, and
+/// the remainders are summed apart.
+pub fn mean<T: Money>(amounts: impl ExactSizeIterator<Item = T>) -> Option<T> {
+    let count = amounts.len() as i128;
+    if count == 0 {
+        return None;
+    }
+
+    let (quotients, remainders) =
+        amounts.fold((0i128, 0i128), |(quotients, remainders), amount| {
+            (
+                quotients + amount.minor() / count,
+                remainders + amount.minor() % count,
+            )
+        });
+    let mean = quotients + remainders.div_euclid(count);
+    let mean = T::try_from_minor(mean).expect("the mean is between the extreme amounts");
+    Some(mean)
+}
+
+// Number of minor units in one unit of `T`, such as 100 cents in a euro
+fn minor_per_unit<T: Money>() -> u64 {
+    10u64.pow(T::minor_digits())
+}
+
+// Convert a dollar value into minor units of `T`, rounded to the closest one
+fn minor_from_dollars<T: Money>(dollars: f64) -> Result<i128, MoneyError> {
+    let minor = (dollars / T::exchange_rate() * minor_per_unit::<T>() as f64).round();
+    if !minor.is_finite() {
+        return Err(MoneyError::NotFinite);
+    }
+    // i128::MAX is rounded up to 2^127 as a f64, which is already too big
+    if minor < i128::MIN as f64 || minor >= i128::MAX as f64 {
+        return Err(MoneyError::OutOfRange);
+    }
+    Ok(minor as i128)
+}
+
+// Create an amount of `T` from a number of units, or fail if `T` cannot represent it
+fn from_units<T: Money>(units: u64) -> Result<T, MoneyError> {
+    (units as i128)
+        .checked_mul(minor_per_unit::<T>() as i128)
+        .and_then(T::try_from_minor)
+        .ok_or(MoneyError::OutOfRange)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn euros(dollars: f64) -> Euro {
+        Euro::try_from(dollars).unwrap()
+    }
+
+    fn dollars(dollars: f64) -> Dollar {
+        Dollar::new(dollars).unwrap()
+    }
+
+    #[test]
+    fn same_currency() {
+        assert_eq!(dollars(1.5) + dollars(2.), dollars(3.5));
+        assert_eq!(dollars(1.5) - dollars(2.), dollars(-0.5));
+        assert_eq!(-dollars(1.5), dollars(-1.5));
+        assert_eq!(
+            Euro::new(3).unwrap() - Euro::new(1).unwrap(),
+            Euro::new(2).unwrap()
+        );
+        assert_eq!(Ouguiya::new(2).unwrap() * 3, Ouguiya::new(6).unwrap());
+        assert!(euros(1.17) < Euro::new(2).unwrap());
+
+        let sum: Euro = vec![Euro::new(1).unwrap(), euros(0.117)].into_iter().sum();
+        assert_eq!(sum.to_string(), "1,10 €");
+    }
+
+    #[test]
+    fn checked() {
+        assert_eq!(
+            Ouguiya::from_minor(1).checked_sub(Ouguiya::from_minor(2)),
+            None
+        );
+        assert_eq!(
+            Ouguiya::from_minor(u64::MAX).checked_add(Ouguiya::from_minor(1)),
+            None
+        );
+        assert_eq!(
+            Euro::new(1).unwrap().checked_sub(euros(0.117)),
+            Some(euros(1.053))
+        );
+        assert_eq!(Euro::from_minor(u64::MAX / 100).checked_mul(200), None);
+        assert_eq!(dollars(1.5).checked_mul(2), Some(dollars(3.)));
+        assert_eq!(dollars(1.).checked_sub(dollars(2.5)), Some(dollars(-1.5)));
+        assert_eq!(
+            Dollar::from_minor(i128::MIN).checked_sub(dollars(0.01)),
+            None
+        );
+        assert_eq!(Dollar::from_minor(i128::MIN).checked_neg(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "attempt to subtract money with overflow")]
+    fn operators_panic() {
+        let _ = Euro::new(1).unwrap() - Euro::new(2).unwrap();
+    }
+
+    #[test]
+    fn convert_to() {
+        let converted: Result<Euro, _> = dollars(117.).convert_to();
+        assert_eq!(converted, Euro::new(100));
+        assert_eq!(
+            Euro::new(100).unwrap().convert_to::<Ouguiya>(),
+            Ouguiya::new(390)
+        );
+        assert_eq!(
+            dollars(-1.).convert_to::<Euro>(),
+            Err(MoneyError::OutOfRange)
+        );
+    }
+
+    #[test]
+    fn allocate() {
+        let parts = Euro::from_minor(100).allocate(&[1, 1, 1]).unwrap();
+        assert_eq!(
+            parts,
+            vec![
+                Euro::from_minor(34),
+                Euro::from_minor(33),
+                Euro::from_minor(33)
+            ]
+        );
+
+        let parts = Dollar::from_minor(5).allocate(&[3, 0, 7]).unwrap();
+        assert_eq!(
+            parts,
+            vec![
+                Dollar::from_minor(2),
+                Dollar::from_minor(0),
+                Dollar::from_minor(3)
+            ]
+        );
+        let parts = Dollar::from_minor(-5).allocate(&[3, 0, 7]).unwrap();
+        assert_eq!(
+            parts,
+            vec![
+                Dollar::from_minor(-2),
+                Dollar::from_minor(0),
+                Dollar::from_minor(-3)
+            ]
+        );
+
+        let parts = Ouguiya::from_minor(u64::MAX)
+            .allocate(&[u64::MAX, 1])
+            .unwrap();
+        assert_eq!(
+            parts,
+            vec![Ouguiya::from_minor(u64::MAX - 1), Ouguiya::from_minor(1)]
+        );
+        let parts = Dollar::from_minor(i128::MIN).allocate(&[1]).unwrap();
+        assert_eq!(parts, vec![Dollar::from_minor(i128::MIN)]);
+        assert_eq!(Dollar::from_minor(i128::MAX).allocate(&[u64::MAX, 1]), None);
+
+        assert_eq!(Euro::new(1).unwrap().allocate(&[0, 0]), None);
+        assert_eq!(Euro::new(1).unwrap().allocate(&[]), None);
+    }
+
+    #[test]
+    fn split() {
+        let parts = Euro::from_minor(5).split(3).unwrap();
+        assert_eq!(
+            parts,
+            vec![
+                Euro::from_minor(2),
+                Euro::from_minor(2),
+                Euro::from_minor(1)
+            ]
+        );
+        assert_eq!(Euro::new(1).unwrap().split(0), None);
+    }
+
+    #[test]
+    fn mean_of_amounts() {
+        assert_eq!(mean(Vec::<Euro>::new().into_iter()), None);
+        let amounts = vec![
+            Dollar::from_minor(i128::MAX),
+            Dollar::from_minor(i128::MAX - 1),
+            Dollar::from_minor(-3),
+        ];
+        assert_eq!(
+            mean(amounts.into_iter()),
+            Some(Dollar::from_minor(i128::MAX / 3 * 2 - 1))
+        );
+        let amounts = vec![Dollar::from_minor(-7), Dollar::from_minor(2)];
+        assert_eq!(mean(amounts.into_iter()), Some(Dollar::from_minor(-3)));
+    }
+
+    #[test]
+    fn cents() {
+        assert_eq!(euros(1.17 * 12.34).minor(), 1234);
+        assert_eq!(euros(1.17 * 0.05).to_string(), "0,05 €");
+        assert_eq!(Ouguiya::try_from(0.3 * 2.5).unwrap().to_string(), "2,50 UM");
+        assert_eq!(dollars(0.1 + 0.2).to_string(), "$0.30");
+        assert_eq!(dollars(1234.5).to_string(), "$1,234.50");
+        assert_eq!(dollars(-1.).minor(), -100);
+        assert_eq!(
+            Ouguiya::new(u64::MAX / 100).unwrap().minor(),
+            18446744073709551600
+        );
+    }
+
+    #[test]
+    fn out_of_range() {
+        assert_eq!(Euro::try_from(-1.), Err(MoneyError::OutOfRange));
+        assert_eq!(Ouguiya::new(u64::MAX), Err(MoneyError::OutOfRange));
+        assert_eq!(Euro::try_from(1e30), Err(MoneyError::OutOfRange));
+        assert_eq!(Dollar::new(1e40), Err(MoneyError::OutOfRange));
+        assert_eq!(Dollar::new(-1e40), Err(MoneyError::OutOfRange));
+        assert_eq!(Dollar::new(f64::NAN), Err(MoneyError::NotFinite));
+        assert_eq!(Euro::try_from(f64::INFINITY), Err(MoneyError::NotFinite));
+    }
+
+    // Largest amount of minor units a f64 holds exactly, with some room for the
+    // exchange rate
+    const MAX_EXACT: i128 = 1 << 50;
+
+    fn round_trip<T: Money + fmt::Debug>(minor: i128) -> Result<(), TestCaseError> {
+        let money = T::try_from_minor(minor).unwrap();
+        prop_assert_eq!(T::try_from(money.dollar_value()), Ok(money));
+        prop_assert_eq!(
+            (money.amount() * minor_per_unit::<T>() as f64).round() as i128,
+            minor
+        );
+
+        let per_unit = minor_per_unit::<T>() as u128;
+        let magnitude = minor.unsigned_abs();
+        let digits = format!("{}{:02}", magnitude / per_unit, magnitude % per_unit);
+        let written: String = money
+            .to_string()
+            .chars()
+            .filter(char::is_ascii_digit)
+            .collect();
+        prop_assert_eq!(written, digits);
+        Ok(())
+    }
+
+    fn from_dollars<T: Money + fmt::Debug>(dollars: f64) -> Result<(), TestCaseError> {
+        // Half of a minor unit, in dollars
+        let precision = T::exchange_rate() / minor_per_unit::<T>() as f64 / 2.;
+        let money = T::try_from(dollars).unwrap();
+        prop_assert!((money.dollar_value() - dollars).abs() <= precision * (1. + 1e-9));
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn dollar_round_trip(minor in -MAX_EXACT..MAX_EXACT) {
+            round_trip::<Dollar>(minor)?;
+        }
+
+        #[test]
+        fn euro_round_trip(minor in 0..MAX_EXACT) {
+            round_trip::<Euro>(minor)?;
+        }
+
+        #[test]
+        fn ouguiya_round_trip(minor in 0..MAX_EXACT) {
+            round_trip::<Ouguiya>(minor)?;
+        }
+
+        #[test]
+        fn allocation_adds_up(
+            minor in any::<u64>(),
+            ratios in proptest::collection::vec(any::<u64>(), 1..10),
+        ) {
+            prop_assume!(ratios.iter().any(|&ratio| ratio > 0));
+            let parts = Euro::from_minor(minor).allocate(&ratios).unwrap();
+
+            prop_assert_eq!(parts.len(), ratios.len());
+            let sum: u128 = parts.iter().map(|part| part.minor() as u128).sum();
+            prop_assert_eq!(sum, minor as u128);
+            // Each part is within one minor unit of its exact share
+            let total: u128 = ratios.iter().map(|&ratio| ratio as u128).sum();
+            for (part, &ratio) in parts.iter().zip(&ratios) {
+                let floor = minor as u128 * ratio as u128 / total;
+                prop_assert!(part.minor() as u128 == floor || part.minor() as u128 == floor + 1);
+            }
+        }
+
+        #[test]
+        fn dollar_from_dollars(dollars in -1e9f64..1e9) {
+            from_dollars::<Dollar>(dollars)?;
+        }
+
+        #[test]
+        fn euro_from_dollars(dollars in 0f64..1e9) {
+            from_dollars::<Euro>(dollars)?;
+        }
+
+        #[test]
+        fn ouguiya_from_dollars(dollars in 0f64..1e9) {
+            from_dollars::<Ouguiya>(dollars)?;
+        }
+    }
 }
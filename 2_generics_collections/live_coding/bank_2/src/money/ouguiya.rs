@@ -1,12 +1,19 @@
-use super::Money;
+use super::{Locale, Money};
+use std::convert::TryFrom;
 
+// Ouguiyas are divided into 5 khoums, but ISO 4217 still counts them in hundredths
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Ouguiya {
-    amount: u64,
+    minor: u64,
 }
 
 impl Ouguiya {
-    pub fn new(amount: u64) -> Ouguiya {
-        Ouguiya { amount }
+    pub fn new(amount: u64) -> Result<Ouguiya, super::MoneyError> {
+        super::from_units(amount)
+    }
+
+    pub fn from_minor(minor: u64) -> Ouguiya {
+        Ouguiya { minor }
     }
 }
 
@@ -15,23 +22,21 @@ impl Money for Ouguiya {
         0.3
     }
 
-    fn amount(&self) -> f64 {
-        self.amount as f64
+    fn symbol() -> &'static str {
+        "UM"
     }
-}
 
-impl From<f64> for Ouguiya {
-    fn from(amount: f64) -> Self {
-        Ouguiya {
-            amount: (amount / Ouguiya::exchange_rate()) as u64,
-        }
+    fn locale() -> Locale {
+        Locale::fr_fr()
     }
-}
 
-use std::fmt;
+    fn try_from_minor(minor: i128) -> Option<Self> {
+        u64::try_from(minor).ok().map(Ouguiya::from_minor)
+    }
 
-impl fmt::Display for Ouguiya {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} ouguiya", self.amount)
+    fn minor(&self) -> i128 {
+        self.minor as i128
     }
 }
+
+money_operators!(Ouguiya);
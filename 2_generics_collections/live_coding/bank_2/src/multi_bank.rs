@@ -0,0 +1,179 @@
+use crate::bank::BankError;
+use crate::money::{self, Dollar, Money};
+use std::collections::HashMap;
+use std::fmt;
+
+// `Money` cannot be used as a trait object: it needs `Sized` types for
+// `TryFrom<f64>`, `Copy` and the operators. This trait keeps what the bank needs to
+// know about an account, whatever its currency.
+pub trait Holding: fmt::Display {
+    // Represent the value of the money held, in dollars
+    fn dollars(&self) -> f64;
+
+    // Add a dollar value converted into the currency of this holding, or return false
+    // if the currency cannot represent it, if the result overflows, or if the result
+    // is worth more than a `Dollar` can count
+    fn add_dollars(&mut self, dollars: f64) -> bool;
+}
+
+impl<T: Money> Holding for T {
+    fn dollars(&self) -> f64 {
+        self.dollar_value()
+    }
+
+    fn add_dollars(&mut self, dollars: f64) -> bool {
+        match T::try_from(dollars)
+            .ok()
+            .and_then(|amount| self.checked_add(amount))
+            // The mean of a bank counts every holding in dollars
+            .filter(|sum| Dollar::new(sum.dollar_value()).is_ok())
+        {
+            None => false,
+            Some(sum) => {
+                *self = sum;
+                true
+            }
+        }
+    }
+}
+
+/// A bank whose accounts can each be in a different currency
+pub struct MultiBank {
+    accounts: HashMap<String, Box<dyn Holding>>,
+}
+
+impl MultiBank {
+    pub fn new() -> MultiBank {
+        MultiBank {
+            accounts: HashMap::new(),
+        }
+    }
+
+    /// Return true if the account was created successfully in currency `T`, false
+    /// otherwise
+    pub fn add_account<T: Money + 'static>(&mut self, account_name: &str) -> bool {
+        match self.accounts.get(account_name) {
+            None => {
+                self.accounts
+                    .insert(String::from(account_name), Box::new(T::zero()));
+                true
+            }
+            Some(_) => false,
+        }
+    }
+
+    /// Add money of any currency to an account. It is converted into the currency
+    /// of the account through its dollar value.
+    pub fn add_money<T: Money>(
+        &mut self,
+        account_name: &str,
+        money_amount: T,
+    ) -> Result<(), BankError> {
+        let account = self
+            .accounts
+            .get_mut(account_name)
+            .ok_or_else(|| BankError::NoSuchAccount(account_name.to_string()))?;
+        if account.add_dollars(money_amount.dollar_value()) {
+            Ok(())
+        } else {
+            Err(BankError::Overflow(account_name.to_string()))
+        }
+    }
+
+    /// Return the mean value of the accounts in dollars, or None if there is no
+    /// account
+    fn mean(&self) -> Option<Dollar> {
+        // Every holding is kept worth an amount of dollars that a `Dollar` can
+        // count, see `Holding::add_dollars`
+        money::mean(self.accounts.values().map(|current_amount| {
+            Dollar::new(current_amount.dollars()).expect("an account is worth an amount of dollars")
+        }))
+    }
+}
+
+impl Default for MultiBank {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for MultiBank {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.mean() {
+            None => write!(f, "no account holders")?,
+            Some(mean) => write!(f, "mean monies: {}, account holders:", mean)?,
+        }
+        for (account_name, money) in &self.accounts {
+            write!(f, "\n{}: {}", account_name, money)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::{Euro, Ouguiya};
+
+    #[test]
+    fn any_currency() {
+        let mut bank = MultiBank::new();
+        assert!(bank.add_account::<Dollar>("Trisha Paytas"));
+        assert!(bank.add_account::<Euro>("Jojo"));
+        assert!(!bank.add_account::<Ouguiya>("Jojo"));
+
+        assert_eq!(bank.add_money("Jojo", Dollar::new(117.).unwrap()), Ok(()));
+        assert_eq!(bank.add_money("Jojo", Euro::from_minor(100)), Ok(()));
+        assert_eq!(
+            bank.add_money("Trisha Paytas", Euro::from_minor(10000)),
+            Ok(())
+        );
+        assert_eq!(bank.accounts["Jojo"].to_string(), "101,00 €");
+        assert_eq!(bank.accounts["Trisha Paytas"].to_string(), "$117.00");
+        assert_eq!(bank.mean(), Some(Dollar::from_minor(11758)));
+    }
+
+    #[test]
+    fn errors() {
+        let mut bank = MultiBank::new();
+        assert_eq!(bank.mean(), None);
+        bank.add_account::<Dollar>("Trisha Paytas");
+
+        assert_eq!(
+            bank.add_money("Jojo", Euro::from_minor(100)),
+            Err(BankError::NoSuchAccount("Jojo".to_string()))
+        );
+        // Its value in dollars is rounded up to 2^127 cents, which is out of range
+        assert_eq!(
+            bank.add_money("Trisha Paytas", Dollar::from_minor(i128::MAX)),
+            Err(BankError::Overflow("Trisha Paytas".to_string()))
+        );
+        assert_eq!(
+            bank.add_money("Trisha Paytas", Dollar::from_minor(i128::MAX / 2)),
+            Ok(())
+        );
+        assert_eq!(
+            bank.add_money("Trisha Paytas", Dollar::from_minor(i128::MAX / 2)),
+            Err(BankError::Overflow("Trisha Paytas".to_string()))
+        );
+    }
+
+    #[test]
+    fn mean_of_the_largest_accounts() {
+        let mut bank = MultiBank::new();
+        bank.add_account::<Dollar>("Trisha Paytas");
+
+        assert_eq!(
+            bank.add_money("Trisha Paytas", Dollar::from_minor(1 << 126)),
+            Ok(())
+        );
+        // The sum fits an i128, but its value in dollars is rounded up past it
+        assert_eq!(
+            bank.add_money("Trisha Paytas", Dollar::from_minor((1 << 126) - (1 << 73))),
+            Err(BankError::Overflow("Trisha Paytas".to_string()))
+        );
+        assert_eq!(bank.mean(), Some(Dollar::from_minor(1 << 126)));
+        assert!(bank.to_string().starts_with("mean monies: $"));
+    }
+}
@@ -1,4 +1,7 @@
+use super::budget::Budget;
+use super::fx::{Conversion, Rate};
 use super::money::*;
+use std::time::SystemTime;
 use uuid::Uuid;
 
 /// Represent an account with `T` as its currency
@@ -10,6 +13,10 @@ pub struct Account<'a> {
     /// The amount of money storred in this account,
     /// in the currency of this account
     money: Box<dyn Money + 'a>,
+    /// Every conversion made between dollars and the currency of this account
+    conversions: Vec<Conversion>,
+    /// The spending limits of this account
+    budget: Budget,
 }
 
 /// Object representation of an account.
@@ -21,7 +28,7 @@ impl<'a> Account<'a> {
     ///
     /// # Argument
     /// * `name` - the name of the owner for this new account
-    pub fn new<T: Money + 'a>(name: &str) -> Self {
+    pub fn new<T: TypedMoney + 'a>(name: &str) -> Self {
         Self::with_id::<T>(name, &Uuid::new_v4())
     }
     /// Create a new account with a given id
@@ -29,7 +36,7 @@ impl<'a> Account<'a> {
     /// # Arguments
     /// * `name` - the name of the owner for this new account
     /// * `id` - the id which the new account will be using
-    pub fn with_id<T: Money + 'a>(name: &str, id: &Uuid) -> Self {
+    pub fn with_id<T: TypedMoney + 'a>(name: &str, id: &Uuid) -> Self {
         Self::with_amount_and_id::<T>(name, 0., id)
     }
     /// Create a new account with a given amount of money
@@ -37,7 +44,7 @@ impl<'a> Account<'a> {
     /// # Arguments
     /// * `name` - the name of the owner for this new account
     /// * `money` - an amount of money which will be converted into the currency of this account
-    pub fn with_amount<T: Money + 'a>(name: &str, money: f64) -> Self {
+    pub fn with_amount<T: TypedMoney + 'a>(name: &str, money: f64) -> Self {
         Self::with_amount_and_id::<T>(name, money, &Uuid::new_v4())
     }
     /// Create a new account with a given id and amount of money
@@ -46,14 +53,44 @@ impl<'a> Account<'a> {
     /// * `name` - the name of the owner for this new account
     /// * `id` - the id which the new account will be using
     /// * `money` - an amount of money which will be converted into the currency of this account
-    pub fn with_amount_and_id<T: Money + 'a>(name: &str, money: f64, id: &Uuid) -> Self {
+    pub fn with_amount_and_id<T: TypedMoney + 'a>(name: &str, money: f64, id: &Uuid) -> Self {
         Self {
             id: *id,
             name: name.to_string(),
             money: Box::new(T::from(T::exchange_rate() * money)),
+            conversions: Vec::new(),
+            budget: Budget::new(),
         }
     }
 
+    /// Create an account around an already built money object.
+    ///
+    /// # Arguments
+    /// * `name` - the name of the owner for this account
+    /// * `id` - the id which the account will be using
+    /// * `money` - the money storred in this account, in its own currency
+    pub fn with_money(name: &str, id: &Uuid, money: Box<dyn Money + 'a>) -> Self {
+        Self {
+            id: *id,
+            name: name.to_string(),
+            money,
+            conversions: Vec::new(),
+            budget: Budget::new(),
+        }
+    }
+
+    /// Restore the conversions of an account, for example when loading a banck
+    pub(crate) fn with_conversions(mut self, conversions: Vec<Conversion>) -> Self {
+        self.conversions = conversions;
+        self
+    }
+
+    /// Restore the budget of an account, for example when loading a banck
+    pub(crate) fn with_budget(mut self, budget: Budget) -> Self {
+        self.budget = budget;
+        self
+    }
+
     /// Retrieve the amount of money storred in the account is the currency of the account.
     /// /!\ This is not the same as `get_value` as `get_value` retrieve the amount of money
     /// storred in this account converted into dollar.
@@ -65,6 +102,23 @@ impl<'a> Account<'a> {
     pub fn get_value(&self) -> f64 {
         self.money.value()
     }
+    /// Get the ISO 4217 code of the currency of this account
+    pub fn get_currency(&self) -> &str {
+        self.money.currency()
+    }
+    /// Get every conversion made between dollars and the currency of this account,
+    /// oldest first
+    pub fn get_conversions(&self) -> &[Conversion] {
+        &self.conversions
+    }
+    /// Get the spending limits of this account and the withdrawals counted against them
+    pub fn get_budget(&self) -> &Budget {
+        &self.budget
+    }
+    /// Get the spending limits of this account, to change them
+    pub fn budget_mut(&mut self) -> &mut Budget {
+        &mut self.budget
+    }
     /// Get the name of the owner of the account
     pub fn get_name(&self) -> &str {
         &self.name
@@ -80,7 +134,25 @@ impl<'a> Account<'a> {
     /// # Arguments
     /// * `amount` - an amount of money in dollar which will be converted into the currency of this account
     pub fn add_money(&mut self, amount: f64) {
-        self.money.add(amount);
+        self.add_money_at(amount, SystemTime::now());
+    }
+
+    /// Same as `add_money`, the exchange rate being quoted at `now`.
+    pub fn add_money_at(&mut self, amount: f64, now: SystemTime) {
+        let rate = self.current_rate(now);
+        self.add_money_at_rate(amount, &rate);
+    }
+
+    /// Same as `add_money`, converting at the given rate instead of the current
+    /// rate of the currency of this account.
+    ///
+    /// # Arguments
+    /// * `amount` - an amount of money in dollar
+    /// * `rate` - the dollar value of one unit of the currency of this account
+    pub fn add_money_at_rate(&mut self, amount: f64, rate: &Rate) {
+        let converted = amount / rate.dollars;
+        self.money.add_amount(converted);
+        self.record_conversion(true, amount, converted, rate);
     }
 
     /// Retrieve money from this account. The passed amount should be in dollar.
@@ -92,10 +164,70 @@ impl<'a> Account<'a> {
     /// # Returns
     /// The effective amount of money retrieved from this account.
     pub fn retrieve_money(&mut self, amount: f64) -> f64 {
-        self.money.remove(amount);
+        self.retrieve_money_at(amount, SystemTime::now())
+    }
+
+    /// Same as `retrieve_money`, the exchange rate being quoted at `now`.
+    pub fn retrieve_money_at(&mut self, amount: f64, now: SystemTime) -> f64 {
+        let rate = self.current_rate(now);
+        self.retrieve_money_at_rate(amount, &rate)
+    }
+
+    /// Same as `retrieve_money`, converting at the given rate instead of the current
+    /// rate of the currency of this account.
+    ///
+    /// # Arguments
+    /// * `amount` - an amount of money in dollar
+    /// * `rate` - the dollar value of one unit of the currency of this account
+    pub fn retrieve_money_at_rate(&mut self, amount: f64, rate: &Rate) -> f64 {
+        let converted = amount / rate.dollars;
+        self.money.remove_amount(converted);
+        self.record_conversion(false, converted, amount, rate);
         amount
     }
 
+    /// Get the current rate of the currency of this account, as quoted at `now`
+    fn current_rate(&self, now: SystemTime) -> Rate {
+        Rate {
+            dollars: self.money.rate(),
+            at: now,
+        }
+    }
+
+    /// Record a conversion between dollars and the currency of this account, unless
+    /// the account is in dollars.
+    ///
+    /// # Arguments
+    /// * `from_dollar` - whether dollars were converted into the currency of the account,
+    ///   or the other way around
+    /// * `rate` - the dollar value of one unit of the currency of the account
+    fn record_conversion(
+        &mut self,
+        from_dollar: bool,
+        source_amount: f64,
+        target_amount: f64,
+        rate: &Rate,
+    ) {
+        let currency = self.money.currency();
+        if currency == Dollar::CODE {
+            return;
+        }
+
+        let (source, target, rate_value) = if from_dollar {
+            (Dollar::CODE, currency, 1. / rate.dollars)
+        } else {
+            (currency, Dollar::CODE, rate.dollars)
+        };
+        self.conversions.push(Conversion {
+            source: source.to_string(),
+            target: target.to_string(),
+            source_amount,
+            target_amount,
+            rate: rate_value,
+            rate_at: rate.at,
+        });
+    }
+
     /// Rename the account.
     /// In case someone want to change it's name ;)
     pub fn rename(&mut self, new_name: &str) {
@@ -135,6 +267,61 @@ mod account_tests {
         assert_eq!(account.get_value(), 50.);
     }
 
+    #[test]
+    fn create_with_money() {
+        let id = Uuid::new_v4();
+        let account = Account::with_money("account", &id, from_currency("EUR", 10.).unwrap());
+        assert_eq!(account.get_amount(), 10.);
+        assert_eq!(account.get_currency(), "EUR");
+        assert_eq!(*account.get_id(), id);
+    }
+
+    #[test]
+    fn conversions() {
+        let mut account = Account::new::<Euro>("account");
+        let at = SystemTime::UNIX_EPOCH;
+        account.add_money_at(117., at);
+        account.retrieve_money_at(11.7, at);
+
+        let conversions = account.get_conversions();
+        assert_eq!(conversions.len(), 2);
+        assert_eq!(conversions[0].source, "USD");
+        assert_eq!(conversions[0].target, "EUR");
+        assert_eq!(conversions[0].source_amount, 117.);
+        assert!((conversions[0].target_amount - 100.).abs() < 1e-9);
+        assert_eq!(conversions[0].rate, 1. / 1.17);
+        assert_eq!(conversions[0].rate_at, at);
+        assert_eq!(conversions[1].source, "EUR");
+        assert_eq!(conversions[1].target, "USD");
+        assert!((conversions[1].source_amount - 10.).abs() < 1e-9);
+        assert_eq!(conversions[1].target_amount, 11.7);
+        assert_eq!(conversions[1].rate, 1.17);
+    }
+
+    #[test]
+    fn conversions_at_rate() {
+        let mut account = Account::new::<Euro>("account");
+        let at = SystemTime::UNIX_EPOCH;
+        account.add_money_at_rate(120., &Rate { dollars: 1.2, at });
+        assert!((account.get_amount() - 100.).abs() < 1e-9);
+
+        account.retrieve_money_at_rate(11., &Rate { dollars: 1.1, at });
+        assert!((account.get_amount() - 90.).abs() < 1e-9);
+
+        let conversions = account.get_conversions();
+        assert_eq!(conversions[0].rate, 1. / 1.2);
+        assert_eq!(conversions[1].rate, 1.1);
+        assert!((conversions[1].source_amount - 10.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn no_conversion_in_dollar() {
+        let mut account = Account::new::<Dollar>("account");
+        account.add_money(10.);
+        account.retrieve_money(5.);
+        assert!(account.get_conversions().is_empty());
+    }
+
     #[test]
     fn rename() {
         let mut account = Account::new::<Euro>("account");
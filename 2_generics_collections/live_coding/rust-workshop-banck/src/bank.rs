@@ -1,9 +1,21 @@
-use std::{collections::HashMap, fmt};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt,
+    time::{Duration, SystemTime},
+};
 use uuid::Uuid;
 
 use super::account::*;
+use super::budget::{Limit, LimitExceeded, ReportLine};
+use super::calendar::Month;
+use super::clock::{Clock, SystemClock};
+use super::fx::{Conversion, Rate, RateTable, Valuation};
+use super::history::{Lookup, RateError, RateHistory};
+use super::idempotency::*;
+use super::ledger::{Ledger, LedgerError, CASH, EQUITY, FEES};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct NoSuchAccountError {
     id: Uuid,
 }
@@ -20,8 +32,87 @@ impl fmt::Display for NoSuchAccountError {
     }
 }
 
+/// Error of a withdrawal from an account
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum WithdrawalError {
+    NoSuchAccount(NoSuchAccountError),
+    /// A spending limit of the account which blocks withdrawals would be exceeded
+    LimitExceeded(LimitExceeded),
+    /// The ledger of the banck refused to record the withdrawal
+    Ledger(LedgerError),
+}
+
+impl From<NoSuchAccountError> for WithdrawalError {
+    fn from(err: NoSuchAccountError) -> Self {
+        WithdrawalError::NoSuchAccount(err)
+    }
+}
+
+impl From<LedgerError> for WithdrawalError {
+    fn from(err: LedgerError) -> Self {
+        WithdrawalError::Ledger(err)
+    }
+}
+
+impl From<MovementError> for WithdrawalError {
+    fn from(err: MovementError) -> Self {
+        match err {
+            MovementError::NoSuchAccount(err) => WithdrawalError::NoSuchAccount(err),
+            MovementError::Ledger(err) => WithdrawalError::Ledger(err),
+        }
+    }
+}
+
+impl fmt::Display for WithdrawalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WithdrawalError::NoSuchAccount(err) => err.fmt(f),
+            WithdrawalError::LimitExceeded(exceeded) => exceeded.fmt(f),
+            WithdrawalError::Ledger(err) => err.fmt(f),
+        }
+    }
+}
+
+/// Error of a deposit or a fee, which no spending limit applies to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MovementError {
+    NoSuchAccount(NoSuchAccountError),
+    /// The ledger of the banck refused to record the movement
+    Ledger(LedgerError),
+}
+
+impl From<NoSuchAccountError> for MovementError {
+    fn from(err: NoSuchAccountError) -> Self {
+        MovementError::NoSuchAccount(err)
+    }
+}
+
+impl From<LedgerError> for MovementError {
+    fn from(err: LedgerError) -> Self {
+        MovementError::Ledger(err)
+    }
+}
+
+impl fmt::Display for MovementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MovementError::NoSuchAccount(err) => err.fmt(f),
+            MovementError::Ledger(err) => err.fmt(f),
+        }
+    }
+}
+
 pub struct Bank<'a> {
     accounts: HashMap<Uuid, Account<'a>>,
+    /// Outcome of the idempotent requests already processed by this banck
+    requests: RequestCache,
+    /// Double-entry journal of every movement of money, when enabled
+    ledger: Option<Ledger>,
+    clock: Box<dyn Clock + 'a>,
+    /// Past exchange rates, for the conversions made as of a given time
+    history: RateHistory,
+    /// How rates missing from `history` are found
+    lookup: Lookup,
 }
 
 impl<'a> Bank<'a> {
@@ -41,7 +132,7 @@ impl<'a> Bank<'a> {
     ///
     /// # Returns
     /// A result which contains the account if it was found is this banck, an error otherwise.
-    pub fn get_account(&self, account_id: &Uuid) -> Result<&Account, NoSuchAccountError> {
+    pub fn get_account(&self, account_id: &Uuid) -> Result<&Account<'a>, NoSuchAccountError> {
         self.accounts
             .get(account_id)
             .ok_or(NoSuchAccountError::new(*account_id))
@@ -49,9 +140,96 @@ impl<'a> Bank<'a> {
 
     /// Create a new banck without any account.
     pub fn new() -> Self {
+        Self::with_idempotency_window(DEFAULT_WINDOW)
+    }
+
+    /// Create a new banck without any account, remembering idempotent requests
+    /// for the given amount of time.
+    pub fn with_idempotency_window(window: Duration) -> Self {
         Self {
             accounts: HashMap::new(),
+            requests: RequestCache::new(window),
+            ledger: None,
+            clock: Box::new(SystemClock),
+            history: RateHistory::new(),
+            lookup: Lookup::Previous,
+        }
+    }
+
+    /// Change the clock used to timestamp the operations of this banck.
+    pub fn set_clock<C: Clock + 'a>(&mut self, clock: C) {
+        self.clock = Box::new(clock);
+    }
+
+    /// Get the current time, according to the clock of this banck.
+    pub fn now(&self) -> SystemTime {
+        self.clock.now()
+    }
+
+    /// Change the past exchange rates used by the conversions made as of a given time.
+    ///
+    /// # Arguments
+    /// * `history` - the rates of each currency over time
+    /// * `lookup` - how to find the rate at a time no rate was recorded for
+    pub fn set_rate_history(&mut self, history: RateHistory, lookup: Lookup) {
+        self.history = history;
+        self.lookup = lookup;
+    }
+
+    /// Get the past exchange rates known to this banck
+    pub fn rate_history(&self) -> &RateHistory {
+        &self.history
+    }
+
+    /// Get how rates are found at a time no rate was recorded for
+    pub fn rate_lookup(&self) -> Lookup {
+        self.lookup
+    }
+
+    /// Start keeping a double-entry ledger of every movement of money in this banck.
+    /// The money already held by each account is opened against the equity of the banck.
+    ///
+    /// # Returns
+    /// An error, and no ledger kept, if the opening of an account cannot be posted.
+    pub fn enable_double_entry(&mut self) -> Result<(), LedgerError> {
+        if self.ledger.is_some() {
+            return Ok(());
+        }
+
+        let mut ledger = Ledger::default();
+        for account in self.accounts.values() {
+            ledger.open_customer(account, EQUITY)?;
         }
+        self.ledger = Some(ledger);
+        Ok(())
+    }
+
+    /// Get the double-entry ledger of this banck, if it was enabled
+    pub fn ledger(&self) -> Option<&Ledger> {
+        self.ledger.as_ref()
+    }
+
+    /// Replace the ledger of this banck, for example when restoring a banck
+    pub(crate) fn set_ledger(&mut self, ledger: Option<Ledger>) {
+        self.ledger = ledger;
+    }
+
+    /// Iterate over every account of this banck, in no particular order.
+    pub fn accounts(&self) -> impl Iterator<Item = &Account<'a>> {
+        self.accounts.values()
+    }
+
+    pub(crate) fn requests(&self) -> &RequestCache {
+        &self.requests
+    }
+
+    pub(crate) fn requests_mut(&mut self) -> &mut RequestCache {
+        &mut self.requests
+    }
+
+    /// Change the amount of time during which idempotent requests are remembered.
+    pub fn set_idempotency_window(&mut self, window: Duration) {
+        self.requests.set_window(window);
     }
 
     /// Add a new account to this banck.
@@ -60,9 +238,16 @@ impl<'a> Bank<'a> {
     /// * `account` - The account to add to the banck
     ///
     /// # Returns
-    /// The id of the added account.
-    pub fn add_account(&mut self, account: Account<'a>) {
+    /// A result with nothing on success, or an error, and nothing added, if the
+    /// ledger of this banck cannot open the account, for example because an
+    /// account with the same id was already opened.
+    pub fn add_account(&mut self, account: Account<'a>) -> Result<(), LedgerError> {
+        if let Some(ledger) = &mut self.ledger {
+            // The initial amount of the account is deposited in cash
+            ledger.open_customer(&account, CASH)?;
+        }
         self.accounts.insert(*account.get_id(), account);
+        Ok(())
     }
 
     /// Add money to the given account.
@@ -73,16 +258,35 @@ impl<'a> Bank<'a> {
     ///
     /// # Returns
     /// A result with nothing on success, or an error if the account was not found
-    /// is this banck.
+    /// is this banck or if its ledger refused to record the deposit, which is made
+    /// anyway.
     pub fn add_account_money(
         &mut self,
         account_id: &Uuid,
         amount: f64,
-    ) -> Result<(), NoSuchAccountError> {
-        self.accounts
-            .get_mut(account_id)
-            .map(|x| x.add_money(amount))
-            .ok_or(NoSuchAccountError::new(*account_id))
+    ) -> Result<(), MovementError> {
+        self.deposit(account_id, amount, None)
+    }
+
+    /// Add money to the given account, converted at the rate of its currency at a
+    /// past time, as found in the rate history of this banck.
+    ///
+    /// # Arguments
+    /// * `account_id` - The id of the account to add money to.
+    /// * `amount` - The amount of money to add.
+    /// * `as_of` - The time of the rate to convert the money with.
+    ///
+    /// # Returns
+    /// A result with nothing on success, or an error if the account was not found
+    /// is this banck or if no rate is known at that time.
+    pub fn add_account_money_as_of(
+        &mut self,
+        account_id: &Uuid,
+        amount: f64,
+        as_of: SystemTime,
+    ) -> Result<(), RateError> {
+        let rate = self.rate_as_of(account_id, as_of)?;
+        Ok(self.deposit(account_id, amount, Some(rate))?)
     }
 
     /// Retrieve money from the given account.
@@ -92,17 +296,326 @@ impl<'a> Bank<'a> {
     /// * `amount` - The amount of money to retrieve from this account.
     ///
     /// # Returns
-    /// A result containing the amount of money retrieved, or an error if
-    /// the account was not found in this banck.
+    /// A result containing the amount of money retrieved along with the limits
+    /// exceeded which only warn, or an error if the account was not found in this
+    /// banck or if a blocking spending limit of the account would be exceeded.
+    /// See `retrieve_account_money_for`.
     pub fn retrieve_account_money(
         &mut self,
         account_id: &Uuid,
         amount: f64,
-    ) -> Result<f64, NoSuchAccountError> {
+    ) -> Result<(f64, Vec<LimitExceeded>), WithdrawalError> {
+        self.retrieve_account_money_for(account_id, amount, None)
+    }
+
+    /// Retrieve money from the given account, counted against the limit of a
+    /// spending category. The withdrawal is dated by the clock of this banck, which
+    /// decides the month it is counted in.
+    ///
+    /// # Arguments
+    /// * `account_id` - The id of the account to take money from.
+    /// * `amount` - The amount of money to retrieve from this account.
+    /// * `category` - The category the money is spent on, if any.
+    ///
+    /// # Returns
+    /// A result containing the amount of money retrieved along with the limits
+    /// exceeded which only warn, also added to the warnings of the budget of the
+    /// account, or an error if the account was not found in this banck or if a
+    /// blocking spending limit of the account would be exceeded.
+    pub fn retrieve_account_money_for(
+        &mut self,
+        account_id: &Uuid,
+        amount: f64,
+        category: Option<&str>,
+    ) -> Result<(f64, Vec<LimitExceeded>), WithdrawalError> {
+        let warnings = self.authorize(account_id, amount, category)?;
+        let retrieved = self.withdraw(account_id, amount, None, "withdrawal", CASH)?;
+        Ok((retrieved, warnings))
+    }
+
+    /// Retrieve money from the given account, converted at the rate of its currency
+    /// at a past time, as found in the rate history of this banck.
+    ///
+    /// # Arguments
+    /// * `account_id` - The id of the account to take money from.
+    /// * `amount` - The amount of money to retrieve from this account.
+    /// * `as_of` - The time of the rate to convert the money with.
+    ///
+    /// # Returns
+    /// A result containing the amount of money retrieved, or an error if the account
+    /// was not found in this banck or if no rate is known at that time.
+    pub fn retrieve_account_money_as_of(
+        &mut self,
+        account_id: &Uuid,
+        amount: f64,
+        as_of: SystemTime,
+    ) -> Result<f64, RateError> {
+        let rate = self.rate_as_of(account_id, as_of)?;
+        Ok(self.withdraw(account_id, amount, Some(rate), "withdrawal", CASH)?)
+    }
+
+    /// Charge a fee to the given account.
+    ///
+    /// # Arguments
+    /// * `account_id` - The id of the account to take the fee from.
+    /// * `amount` - The amount of the fee.
+    ///
+    /// # Returns
+    /// A result containing the amount of money charged, or an error if
+    /// the account was not found in this banck or if its ledger refused to record
+    /// the fee, which is charged anyway.
+    pub fn charge_fee(&mut self, account_id: &Uuid, amount: f64) -> Result<f64, MovementError> {
+        self.withdraw(account_id, amount, None, "fee", FEES)
+    }
+
+    /// Get the rate of the currency of an account at a past time
+    fn rate_as_of(&self, account_id: &Uuid, as_of: SystemTime) -> Result<Rate, RateError> {
+        let currency = self.get_account(account_id)?.get_currency();
+        self.history
+            .rate(currency, as_of, self.lookup)
+            .ok_or_else(|| RateError::NoRate {
+                currency: currency.to_string(),
+                as_of,
+            })
+    }
+
+    /// Add money to an account, converted at `rate`, or at the current rate of its
+    /// currency if `None`.
+    fn deposit(
+        &mut self,
+        account_id: &Uuid,
+        amount: f64,
+        rate: Option<Rate>,
+    ) -> Result<(), MovementError> {
+        let now = self.clock.now();
+        let account = self
+            .accounts
+            .get_mut(account_id)
+            .ok_or(NoSuchAccountError::new(*account_id))?;
+        let before = account.get_amount();
+
+        match rate {
+            Some(rate) => account.add_money_at_rate(amount, &rate),
+            None => account.add_money_at(amount, now),
+        }
+        if let Some(ledger) = &mut self.ledger {
+            ledger.customer_movement("deposit", account, before, CASH, amount)?;
+        }
+        Ok(())
+    }
+
+    /// Count a withdrawal against the budget of an account, dated by the clock of
+    /// this banck. See `Budget::authorize`.
+    fn authorize(
+        &mut self,
+        account_id: &Uuid,
+        amount: f64,
+        category: Option<&str>,
+    ) -> Result<Vec<LimitExceeded>, WithdrawalError> {
+        let now = self.clock.now();
         self.accounts
             .get_mut(account_id)
-            .map(|x| x.retrieve_money(amount))
-            .ok_or(NoSuchAccountError::new(*account_id))
+            .ok_or(NoSuchAccountError::new(*account_id))?
+            .budget_mut()
+            .authorize(amount, category, now)
+            .map_err(WithdrawalError::LimitExceeded)
+    }
+
+    /// Retrieve money from an account, converted at `rate`, or at the current rate
+    /// of its currency if `None`. The other side of the movement is `counterpart`
+    /// in the ledger.
+    fn withdraw(
+        &mut self,
+        account_id: &Uuid,
+        amount: f64,
+        rate: Option<Rate>,
+        description: &str,
+        counterpart: &str,
+    ) -> Result<f64, MovementError> {
+        let now = self.clock.now();
+        let account = self
+            .accounts
+            .get_mut(account_id)
+            .ok_or(NoSuchAccountError::new(*account_id))?;
+        let before = account.get_amount();
+
+        let retrieved = match rate {
+            Some(rate) => account.retrieve_money_at_rate(amount, &rate),
+            None => account.retrieve_money_at(amount, now),
+        };
+        if let Some(ledger) = &mut self.ledger {
+            ledger.customer_movement(description, account, before, counterpart, -retrieved)?;
+        }
+        Ok(retrieved)
+    }
+
+    /// Move money from one account to another.
+    ///
+    /// # Arguments
+    /// * `from` - The id of the account to take money from.
+    /// * `to` - The id of the account to give money to.
+    /// * `amount` - The amount of money to transfer.
+    ///
+    /// # Returns
+    /// A result containing the amount of money transferred, or an error if
+    /// either account was not found in this banck or if a blocking spending limit
+    /// of `from` would be exceeded, in which case nothing is moved. Limits which
+    /// only warn are added to the warnings of the budget of `from`. An error is
+    /// also returned if the ledger of this banck refused to record the transfer,
+    /// which is made anyway.
+    pub fn transfer(
+        &mut self,
+        from: &Uuid,
+        to: &Uuid,
+        amount: f64,
+    ) -> Result<f64, WithdrawalError> {
+        let to_before = self.get_account(to)?.get_amount();
+        let from_before = self.get_account(from)?.get_amount();
+        self.authorize(from, amount, None)?;
+
+        let now = self.clock.now();
+        let retrieved = self
+            .accounts
+            .get_mut(from)
+            .unwrap()
+            .retrieve_money_at(amount, now);
+        self.accounts
+            .get_mut(to)
+            .unwrap()
+            .add_money_at(retrieved, now);
+        if let Some(ledger) = &mut self.ledger {
+            ledger.transfer(
+                &self.accounts[from],
+                from_before,
+                &self.accounts[to],
+                to_before,
+            )?;
+        }
+        Ok(retrieved)
+    }
+
+    /// Idempotent version of `add_account_money`.
+    ///
+    /// # Arguments
+    /// * `request_id` - An id chosen by the client, the same for every retry of this request.
+    /// * `account_id` - The id of the account to add money to.
+    /// * `amount` - The amount of money to add.
+    ///
+    /// # Returns
+    /// The result of the first call made with this `request_id`. The deposit is only
+    /// applied once while the request is remembered, and a retry with other
+    /// arguments is refused.
+    pub fn add_account_money_idempotent(
+        &mut self,
+        request_id: &Uuid,
+        account_id: &Uuid,
+        amount: f64,
+    ) -> Result<(), RequestError> {
+        let now = self.clock.now();
+        let request = Request::Deposit {
+            account: *account_id,
+            amount,
+        };
+        match self.requests.get(request_id, now) {
+            Some(Record {
+                request: previous,
+                outcome: Outcome::Deposit(result),
+                ..
+            }) if *previous == request => Ok(result.clone()?),
+            Some(_) => Err(RequestError::ReusedRequestId(*request_id)),
+            None => {
+                let result = self.add_account_money(account_id, amount);
+                self.requests
+                    .insert(request_id, request, Outcome::Deposit(result.clone()), now);
+                Ok(result?)
+            }
+        }
+    }
+
+    /// Idempotent version of `retrieve_account_money_for`, without a category.
+    ///
+    /// # Arguments
+    /// * `request_id` - An id chosen by the client, the same for every retry of this request.
+    /// * `account_id` - The id of the account to take money from.
+    /// * `amount` - The amount of money to retrieve from this account.
+    ///
+    /// # Returns
+    /// The result of the first call made with this `request_id`. The withdrawal is only
+    /// applied once while the request is remembered, and a retry with other
+    /// arguments is refused. Limits which only warn are added to the warnings of the
+    /// budget of the account.
+    pub fn retrieve_account_money_idempotent(
+        &mut self,
+        request_id: &Uuid,
+        account_id: &Uuid,
+        amount: f64,
+    ) -> Result<f64, RequestError> {
+        let now = self.clock.now();
+        let request = Request::Withdrawal {
+            account: *account_id,
+            amount,
+        };
+        match self.requests.get(request_id, now) {
+            Some(Record {
+                request: previous,
+                outcome: Outcome::Withdrawal(result),
+                ..
+            }) if *previous == request => Ok(result.clone()?),
+            Some(_) => Err(RequestError::ReusedRequestId(*request_id)),
+            None => {
+                let result = self
+                    .retrieve_account_money_for(account_id, amount, None)
+                    .map(|(retrieved, _)| retrieved);
+                self.requests.insert(
+                    request_id,
+                    request,
+                    Outcome::Withdrawal(result.clone()),
+                    now,
+                );
+                Ok(result?)
+            }
+        }
+    }
+
+    /// Idempotent version of `transfer`.
+    ///
+    /// # Arguments
+    /// * `request_id` - An id chosen by the client, the same for every retry of this request.
+    /// * `from` - The id of the account to take money from.
+    /// * `to` - The id of the account to give money to.
+    /// * `amount` - The amount of money to transfer.
+    ///
+    /// # Returns
+    /// The result of the first call made with this `request_id`. The transfer is only
+    /// applied once while the request is remembered, and a retry with other
+    /// arguments is refused.
+    pub fn transfer_idempotent(
+        &mut self,
+        request_id: &Uuid,
+        from: &Uuid,
+        to: &Uuid,
+        amount: f64,
+    ) -> Result<f64, RequestError> {
+        let now = self.clock.now();
+        let request = Request::Transfer {
+            from: *from,
+            to: *to,
+            amount,
+        };
+        match self.requests.get(request_id, now) {
+            Some(Record {
+                request: previous,
+                outcome: Outcome::Transfer(result),
+                ..
+            }) if *previous == request => Ok(result.clone()?),
+            Some(_) => Err(RequestError::ReusedRequestId(*request_id)),
+            None => {
+                let result = self.transfer(from, to, amount);
+                self.requests
+                    .insert(request_id, request, Outcome::Transfer(result.clone()), now);
+                Ok(result?)
+            }
+        }
     }
 
     /// Get the amount of money storred in the given account.
@@ -117,6 +630,102 @@ impl<'a> Bank<'a> {
         Ok(self.get_account(account_id)?.get_value())
     }
 
+    /// Value again every currency conversion made on the given account.
+    ///
+    /// # Arguments
+    /// * `account_id` - The id of the account whose conversions to revalue.
+    /// * `valuation` - The rates to use: the original ones, or today's rates.
+    ///
+    /// # Returns
+    /// A result containing the conversions, oldest first, or an error if the account
+    /// was not found in this banck. Conversions between currencies missing from
+    /// today's rates keep their original rate.
+    pub fn revalue(
+        &self,
+        account_id: &Uuid,
+        valuation: Valuation,
+    ) -> Result<Vec<Conversion>, NoSuchAccountError> {
+        let conversions = self.get_account(account_id)?.get_conversions();
+
+        Ok(match valuation {
+            Valuation::Original => conversions.to_vec(),
+            Valuation::Today(rates) => conversions
+                .iter()
+                .map(|conversion| rates.revalue(conversion))
+                .collect(),
+        })
+    }
+
+    /// Get the unrealised gain, or loss, of the conversions of the given account
+    /// if they were made at today's rates.
+    ///
+    /// # Returns
+    /// A result containing the gain in dollar, negative for a loss, or an error if
+    /// the account was not found in this banck.
+    pub fn unrealised_gain(
+        &self,
+        account_id: &Uuid,
+        rates: &RateTable,
+    ) -> Result<f64, NoSuchAccountError> {
+        let original = self.revalue(account_id, Valuation::Original)?;
+        let today = self.revalue(account_id, Valuation::Today(rates))?;
+
+        Ok(original
+            .iter()
+            .zip(today.iter())
+            .map(|(original, today)| {
+                let target_rate = rates
+                    .get(&original.target)
+                    .map(|rate| rate.dollars)
+                    .unwrap_or(0.);
+                (original.target_amount - today.target_amount) * target_rate
+            })
+            .sum())
+    }
+
+    /// Set, or remove with `None`, a spending limit of the given account.
+    ///
+    /// # Arguments
+    /// * `account_id` - The id of the account to limit.
+    /// * `category` - The category to limit, or `None` for the monthly budget of the
+    ///   account, which covers every withdrawal.
+    /// * `limit` - The amount which can be spent each month, and what happens to the
+    ///   withdrawals going over it.
+    ///
+    /// # Returns
+    /// A result with nothing on success, or an error if the account was not found
+    /// is this banck.
+    pub fn set_spending_limit(
+        &mut self,
+        account_id: &Uuid,
+        category: Option<&str>,
+        limit: Option<Limit>,
+    ) -> Result<(), NoSuchAccountError> {
+        self.accounts
+            .get_mut(account_id)
+            .map(|account| account.budget_mut().set_limit(category, limit))
+            .ok_or(NoSuchAccountError::new(*account_id))
+    }
+
+    /// Get the spending against every limit of the given account during the current
+    /// month, according to the clock of this banck.
+    pub fn budget_report(&self, account_id: &Uuid) -> Result<Vec<ReportLine>, NoSuchAccountError> {
+        self.budget_report_of(account_id, Month::containing(self.clock.now()))
+    }
+
+    /// Get the spending against every limit of the given account during a month.
+    ///
+    /// # Returns
+    /// A result containing the monthly budget first, if any, then the limit of each
+    /// category, or an error if the account was not found in this banck.
+    pub fn budget_report_of(
+        &self,
+        account_id: &Uuid,
+        month: Month,
+    ) -> Result<Vec<ReportLine>, NoSuchAccountError> {
+        Ok(self.get_account(account_id)?.get_budget().report(month))
+    }
+
     /// Rename the account.
     ///
     /// > Just in case someone wants to change name.
@@ -132,15 +741,27 @@ impl<'a> Bank<'a> {
     }
 }
 
+impl<'a> Default for Bank<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::super::budget::Enforcement;
+    use super::super::calendar::date;
+    use super::super::clock::ManualClock;
+    use super::super::ledger::{customer_code, to_minor, EXCHANGE};
     use super::super::money::*;
     use super::*;
     #[test]
     fn add_account() {
         let mut banck = Bank::new();
         let id = Uuid::new_v4();
-        banck.add_account(Account::with_id::<Euro>("account", &id));
+        banck
+            .add_account(Account::with_id::<Euro>("account", &id))
+            .unwrap();
 
         assert!(banck.get_account(&id).is_ok());
         assert_eq!(banck.get_account(&id).unwrap().get_name(), "account");
@@ -151,8 +772,10 @@ mod tests {
         let mut bank = Bank::new();
         let id = Uuid::new_v4();
         let id2 = Uuid::new_v4();
-        bank.add_account(Account::with_id::<Euro>("account1", &id));
-        bank.add_account(Account::with_id::<Dollar>("account2", &id2));
+        bank.add_account(Account::with_id::<Euro>("account1", &id))
+            .unwrap();
+        bank.add_account(Account::with_id::<Dollar>("account2", &id2))
+            .unwrap();
 
         assert!(bank.get_account(&id).is_ok());
         assert_eq!(bank.get_account(&id).unwrap().get_name(), "account1");
@@ -169,8 +792,8 @@ mod tests {
             let account1 = Account::with_id::<Euro>("account1", &id1);
             let account2 = Account::with_id::<Dollar>("account2", &id2);
 
-            universal_bank.add_account(account1);
-            universal_bank.add_account(account2);
+            universal_bank.add_account(account1).unwrap();
+            universal_bank.add_account(account2).unwrap();
         }
 
         assert!(universal_bank.get_account(&id1).is_ok());
@@ -189,7 +812,9 @@ mod tests {
     fn get_fake_account() {
         let mut banck = Bank::new();
         let id = Uuid::new_v4();
-        banck.add_account(Account::with_id::<Euro>("account", &id));
+        banck
+            .add_account(Account::with_id::<Euro>("account", &id))
+            .unwrap();
 
         assert!(banck.get_account(&Uuid::new_v4()).is_err());
         let id = Uuid::new_v4();
@@ -200,7 +825,9 @@ mod tests {
     fn add_money_real_account() {
         let mut banck = Bank::new();
         let id = Uuid::new_v4();
-        banck.add_account(Account::with_id::<Dollar>("account", &id));
+        banck
+            .add_account(Account::with_id::<Dollar>("account", &id))
+            .unwrap();
 
         assert!(banck.add_account_money(&id, 10.).is_ok());
         assert_eq!(banck.get_account_money(&id).unwrap(), 10.);
@@ -211,8 +838,12 @@ mod tests {
         let mut universal_bank = Bank::new();
         let id = Uuid::new_v4();
         let id2 = Uuid::new_v4();
-        universal_bank.add_account(Account::with_id::<Euro>("account1", &id));
-        universal_bank.add_account(Account::with_id::<Dollar>("account2", &id2));
+        universal_bank
+            .add_account(Account::with_id::<Euro>("account1", &id))
+            .unwrap();
+        universal_bank
+            .add_account(Account::with_id::<Dollar>("account2", &id2))
+            .unwrap();
 
         assert!(universal_bank.add_account_money(&id, 100.).is_ok());
         assert_eq!(universal_bank.get_account_money(&id).unwrap(), 100.);
@@ -231,10 +862,12 @@ mod tests {
     fn retrieve_money_real_account() {
         let mut banck = Bank::new();
         let id = Uuid::new_v4();
-        banck.add_account(Account::with_id::<Euro>("account", &id));
+        banck
+            .add_account(Account::with_id::<Euro>("account", &id))
+            .unwrap();
 
         assert!(banck.add_account_money(&id, 10.).is_ok());
-        assert_eq!(banck.retrieve_account_money(&id, 5.).unwrap(), 5.);
+        assert_eq!(banck.retrieve_account_money(&id, 5.).unwrap().0, 5.);
         assert_eq!(banck.get_account_money(&id).unwrap(), 5.);
     }
 
@@ -248,7 +881,9 @@ mod tests {
     fn rename_real_account() {
         let mut banck = Bank::new();
         let id = Uuid::new_v4();
-        banck.add_account(Account::with_id::<Euro>("account", &id));
+        banck
+            .add_account(Account::with_id::<Euro>("account", &id))
+            .unwrap();
 
         assert!(banck.rename_account(&id, "hello").is_ok());
         assert_eq!(banck.get_account(&id).unwrap().get_name(), "hello");
@@ -259,4 +894,474 @@ mod tests {
         let mut banck = Bank::new();
         assert!(banck.rename_account(&Uuid::new_v4(), "hello").is_err());
     }
+
+    #[test]
+    fn transfer() {
+        let mut banck = Bank::new();
+        let from = Uuid::new_v4();
+        let to = Uuid::new_v4();
+        banck
+            .add_account(Account::with_amount_and_id::<Dollar>("from", 10., &from))
+            .unwrap();
+        banck
+            .add_account(Account::with_id::<Dollar>("to", &to))
+            .unwrap();
+
+        assert_eq!(banck.transfer(&from, &to, 4.).unwrap(), 4.);
+        assert_eq!(banck.get_account_money(&from).unwrap(), 6.);
+        assert_eq!(banck.get_account_money(&to).unwrap(), 4.);
+    }
+
+    #[test]
+    fn transfer_fake_account() {
+        let mut banck = Bank::new();
+        let from = Uuid::new_v4();
+        banck
+            .add_account(Account::with_amount_and_id::<Dollar>("from", 10., &from))
+            .unwrap();
+
+        assert!(banck.transfer(&from, &Uuid::new_v4(), 4.).is_err());
+        assert_eq!(banck.get_account_money(&from).unwrap(), 10.);
+    }
+
+    #[test]
+    fn deposit_replayed() {
+        let mut banck = Bank::new();
+        let id = Uuid::new_v4();
+        let request = Uuid::new_v4();
+        banck
+            .add_account(Account::with_id::<Dollar>("account", &id))
+            .unwrap();
+
+        assert!(banck
+            .add_account_money_idempotent(&request, &id, 10.)
+            .is_ok());
+        assert!(banck
+            .add_account_money_idempotent(&request, &id, 10.)
+            .is_ok());
+        assert_eq!(banck.get_account_money(&id).unwrap(), 10.);
+    }
+
+    #[test]
+    fn withdrawal_replayed() {
+        let mut banck = Bank::new();
+        let id = Uuid::new_v4();
+        let request = Uuid::new_v4();
+        banck
+            .add_account(Account::with_amount_and_id::<Dollar>("account", 10., &id))
+            .unwrap();
+
+        assert_eq!(
+            banck.retrieve_account_money_idempotent(&request, &id, 3.),
+            Ok(3.)
+        );
+        assert_eq!(
+            banck.retrieve_account_money_idempotent(&request, &id, 3.),
+            Ok(3.)
+        );
+        assert_eq!(banck.get_account_money(&id).unwrap(), 7.);
+    }
+
+    #[test]
+    fn transfer_replayed() {
+        let mut banck = Bank::new();
+        let from = Uuid::new_v4();
+        let to = Uuid::new_v4();
+        let request = Uuid::new_v4();
+        banck
+            .add_account(Account::with_amount_and_id::<Dollar>("from", 10., &from))
+            .unwrap();
+        banck
+            .add_account(Account::with_id::<Dollar>("to", &to))
+            .unwrap();
+
+        assert_eq!(banck.transfer_idempotent(&request, &from, &to, 4.), Ok(4.));
+        assert_eq!(banck.transfer_idempotent(&request, &from, &to, 4.), Ok(4.));
+        assert_eq!(banck.get_account_money(&from).unwrap(), 6.);
+        assert_eq!(banck.get_account_money(&to).unwrap(), 4.);
+    }
+
+    #[test]
+    fn failure_replayed() {
+        let mut banck = Bank::new();
+        let id = Uuid::new_v4();
+        let request = Uuid::new_v4();
+
+        assert!(banck
+            .add_account_money_idempotent(&request, &id, 10.)
+            .is_err());
+        banck
+            .add_account(Account::with_id::<Dollar>("account", &id))
+            .unwrap();
+        assert_eq!(
+            banck.add_account_money_idempotent(&request, &id, 10.),
+            Err(RequestError::NoSuchAccount(NoSuchAccountError::new(id)))
+        );
+        assert_eq!(banck.get_account_money(&id).unwrap(), 0.);
+    }
+
+    #[test]
+    fn request_id_reused() {
+        let mut banck = Bank::new();
+        let id = Uuid::new_v4();
+        let request = Uuid::new_v4();
+        banck
+            .add_account(Account::with_id::<Dollar>("account", &id))
+            .unwrap();
+
+        assert!(banck
+            .add_account_money_idempotent(&request, &id, 10.)
+            .is_ok());
+        assert_eq!(
+            banck.retrieve_account_money_idempotent(&request, &id, 10.),
+            Err(RequestError::ReusedRequestId(request))
+        );
+        assert_eq!(banck.get_account_money(&id).unwrap(), 10.);
+    }
+
+    #[test]
+    fn request_id_reused_with_other_arguments() {
+        let mut banck = Bank::new();
+        let from = Uuid::new_v4();
+        let to = Uuid::new_v4();
+        let request = Uuid::new_v4();
+        banck
+            .add_account(Account::with_amount_and_id::<Dollar>("from", 10., &from))
+            .unwrap();
+        banck
+            .add_account(Account::with_id::<Dollar>("to", &to))
+            .unwrap();
+
+        assert_eq!(banck.transfer_idempotent(&request, &from, &to, 4.), Ok(4.));
+        assert_eq!(
+            banck.transfer_idempotent(&request, &from, &to, 5.),
+            Err(RequestError::ReusedRequestId(request))
+        );
+        assert_eq!(
+            banck.transfer_idempotent(&request, &to, &from, 4.),
+            Err(RequestError::ReusedRequestId(request))
+        );
+        assert_eq!(banck.get_account_money(&from).unwrap(), 6.);
+        assert_eq!(banck.get_account_money(&to).unwrap(), 4.);
+    }
+
+    #[test]
+    fn request_forgotten_after_window() {
+        let mut banck = Bank::with_idempotency_window(Duration::from_secs(0));
+        let id = Uuid::new_v4();
+        let request = Uuid::new_v4();
+        banck
+            .add_account(Account::with_id::<Dollar>("account", &id))
+            .unwrap();
+
+        assert!(banck
+            .add_account_money_idempotent(&request, &id, 10.)
+            .is_ok());
+        assert!(banck
+            .add_account_money_idempotent(&request, &id, 10.)
+            .is_ok());
+        assert_eq!(banck.get_account_money(&id).unwrap(), 20.);
+    }
+
+    #[test]
+    fn charge_fee() {
+        let mut banck = Bank::new();
+        let id = Uuid::new_v4();
+        banck
+            .add_account(Account::with_amount_and_id::<Dollar>("account", 10., &id))
+            .unwrap();
+
+        assert_eq!(banck.charge_fee(&id, 2.).unwrap(), 2.);
+        assert_eq!(banck.get_account_money(&id).unwrap(), 8.);
+        assert!(banck.charge_fee(&Uuid::new_v4(), 2.).is_err());
+    }
+
+    #[test]
+    fn double_entry() {
+        let mut banck = Bank::new();
+        banck.enable_double_entry().unwrap();
+        let dollars = Uuid::new_v4();
+        let euros = Uuid::new_v4();
+        let ouguiyas = Uuid::new_v4();
+        banck
+            .add_account(Account::with_amount_and_id::<Dollar>(
+                "dollars", 100., &dollars,
+            ))
+            .unwrap();
+        banck
+            .add_account(Account::with_id::<Euro>("euros", &euros))
+            .unwrap();
+        banck
+            .add_account(Account::with_id::<Ouguiya>("ouguiyas", &ouguiyas))
+            .unwrap();
+
+        banck.add_account_money(&euros, 50.).unwrap();
+        banck.transfer(&dollars, &euros, 10.).unwrap();
+        banck.transfer(&euros, &ouguiyas, 33.33).unwrap();
+        banck.retrieve_account_money(&ouguiyas, 3.).unwrap();
+        banck.charge_fee(&dollars, 1.).unwrap();
+
+        let ledger = banck.ledger().unwrap();
+        assert!(ledger.check().is_ok());
+        assert_eq!(ledger.balance(CASH, "USD"), to_minor(150. - 3., "USD"));
+        assert_eq!(ledger.balance(FEES, "USD"), -100);
+        for id in [dollars, euros, ouguiyas].iter() {
+            let account = banck.get_account(id).unwrap();
+            assert_eq!(
+                ledger.balance(&customer_code(id), account.get_currency()),
+                -to_minor(account.get_amount(), account.get_currency())
+            );
+        }
+    }
+
+    #[test]
+    fn double_entry_opening_balances() {
+        let mut banck = Bank::new();
+        let id = Uuid::new_v4();
+        banck
+            .add_account(Account::with_amount_and_id::<Euro>("account", 100., &id))
+            .unwrap();
+        banck.enable_double_entry().unwrap();
+
+        let ledger = banck.ledger().unwrap();
+        assert!(ledger.check().is_ok());
+        assert_eq!(ledger.balance(EQUITY, "USD"), 11700);
+        assert_eq!(ledger.balance(&customer_code(&id), "EUR"), -10000);
+        assert_eq!(ledger.balance(EXCHANGE, "USD"), -11700);
+        assert_eq!(ledger.balance(EXCHANGE, "EUR"), 10000);
+    }
+
+    #[test]
+    fn double_entry_account_added_twice() {
+        let mut banck = Bank::new();
+        banck.enable_double_entry().unwrap();
+        let id = Uuid::new_v4();
+        banck
+            .add_account(Account::with_amount_and_id::<Dollar>("account", 100., &id))
+            .unwrap();
+
+        assert_eq!(
+            banck.add_account(Account::with_amount_and_id::<Dollar>("again", 100., &id)),
+            Err(LedgerError::AccountExists(customer_code(&id)))
+        );
+        assert_eq!(banck.get_account(&id).unwrap().get_name(), "account");
+        let ledger = banck.ledger().unwrap();
+        assert_eq!(ledger.balance(&customer_code(&id), "USD"), -10000);
+        assert_eq!(ledger.balance(CASH, "USD"), 10000);
+    }
+
+    #[test]
+    fn conversions_use_clock() {
+        let mut banck = Bank::new();
+        let clock = ManualClock::new(SystemTime::UNIX_EPOCH);
+        banck.set_clock(clock.clone());
+        let id = Uuid::new_v4();
+        banck
+            .add_account(Account::with_id::<Euro>("account", &id))
+            .unwrap();
+
+        clock.advance(Duration::from_secs(60));
+        banck.add_account_money(&id, 117.).unwrap();
+
+        let conversions = banck.revalue(&id, Valuation::Original).unwrap();
+        assert_eq!(conversions.len(), 1);
+        assert_eq!(
+            conversions[0].rate_at,
+            SystemTime::UNIX_EPOCH + Duration::from_secs(60)
+        );
+    }
+
+    #[test]
+    fn conversions_as_of() {
+        let mut banck = Bank::new();
+        banck.enable_double_entry().unwrap();
+        let id = Uuid::new_v4();
+        let dollars_id = Uuid::new_v4();
+        banck
+            .add_account(Account::with_id::<Euro>("account", &id))
+            .unwrap();
+        banck
+            .add_account(Account::with_id::<Dollar>("dollars", &dollars_id))
+            .unwrap();
+
+        let day = |days: u64| SystemTime::UNIX_EPOCH + Duration::from_secs(days * 86400);
+        let mut history = RateHistory::new();
+        history.insert("EUR", day(10), 1.2);
+        history.insert("EUR", day(20), 1.4);
+        banck.set_rate_history(history, Lookup::Previous);
+
+        banck.add_account_money_as_of(&id, 120., day(15)).unwrap();
+        assert!((banck.get_account(&id).unwrap().get_amount() - 100.).abs() < 1e-9);
+        banck
+            .retrieve_account_money_as_of(&id, 14., day(25))
+            .unwrap();
+        assert!((banck.get_account(&id).unwrap().get_amount() - 90.).abs() < 1e-9);
+
+        let conversions = banck.revalue(&id, Valuation::Original).unwrap();
+        assert_eq!(conversions[0].rate_at, day(10));
+        assert_eq!(conversions[1].rate, 1.4);
+        assert_eq!(banck.ledger().unwrap().check(), Ok(()));
+
+        assert_eq!(
+            banck.add_account_money_as_of(&id, 1., day(5)),
+            Err(RateError::NoRate {
+                currency: "EUR".to_string(),
+                as_of: day(5)
+            })
+        );
+        banck
+            .add_account_money_as_of(&dollars_id, 1., day(5))
+            .unwrap();
+        assert_eq!(banck.get_account_money(&dollars_id), Ok(1.));
+
+        banck.set_rate_history(banck.rate_history().clone(), Lookup::Interpolated);
+        banck.add_account_money_as_of(&id, 13., day(15)).unwrap();
+        assert!((banck.get_account(&id).unwrap().get_amount() - 100.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn spending_limits() {
+        let mut banck = Bank::new();
+        let clock = ManualClock::new(date(2021, 3, 31).unwrap());
+        banck.set_clock(clock.clone());
+        let id = Uuid::new_v4();
+        banck
+            .add_account(Account::with_amount_and_id::<Dollar>("account", 1000., &id))
+            .unwrap();
+
+        let limit = |amount, enforcement| {
+            Some(Limit {
+                amount,
+                enforcement,
+            })
+        };
+        banck
+            .set_spending_limit(&id, None, limit(300., Enforcement::Block))
+            .unwrap();
+        banck
+            .set_spending_limit(&id, Some("food"), limit(100., Enforcement::Warn))
+            .unwrap();
+        assert!(banck
+            .set_spending_limit(&Uuid::new_v4(), None, None)
+            .is_err());
+
+        let (retrieved, warnings) = banck
+            .retrieve_account_money_for(&id, 150., Some("food"))
+            .unwrap();
+        assert_eq!(retrieved, 150.);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].category.as_deref(), Some("food"));
+        assert_eq!(
+            banck
+                .get_account(&id)
+                .unwrap()
+                .get_budget()
+                .warnings()
+                .len(),
+            1
+        );
+        assert_eq!(
+            banck.retrieve_account_money_for(&id, 100., None),
+            Ok((100., vec![]))
+        );
+        match banck.retrieve_account_money_for(&id, 100., None) {
+            Err(WithdrawalError::LimitExceeded(exceeded)) => {
+                assert_eq!(exceeded.category, None);
+                assert_eq!(exceeded.spent, 250.);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(banck.get_account_money(&id).unwrap(), 750.);
+
+        let report = banck.budget_report(&id).unwrap();
+        assert_eq!(report[0].remaining(), 50.);
+        assert_eq!(report[1].category.as_deref(), Some("food"));
+        assert_eq!(report[1].spent, 150.);
+
+        // A new month starts with nothing spent
+        clock.advance(Duration::from_secs(24 * 60 * 60));
+        assert_eq!(
+            banck.retrieve_account_money_for(&id, 300., None),
+            Ok((300., vec![]))
+        );
+        assert_eq!(banck.budget_report(&id).unwrap()[0].remaining(), 0.);
+        let march = Month::new(2021, 3).unwrap();
+        assert_eq!(banck.budget_report_of(&id, march).unwrap()[0].spent, 250.);
+
+        // Withdrawals without a category are counted against the monthly limit
+        assert!(matches!(
+            banck.retrieve_account_money(&id, 50.),
+            Err(WithdrawalError::LimitExceeded(_))
+        ));
+        assert_eq!(banck.budget_report(&id).unwrap()[0].spent, 300.);
+        banck
+            .set_spending_limit(&id, None, limit(300., Enforcement::Warn))
+            .unwrap();
+        let (retrieved, warnings) = banck.retrieve_account_money(&id, 50.).unwrap();
+        assert_eq!(retrieved, 50.);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].spent, 300.);
+        assert_eq!(banck.budget_report(&id).unwrap()[0].spent, 350.);
+    }
+
+    #[test]
+    fn transfers_are_budgeted() {
+        let mut banck = Bank::new();
+        let from = Uuid::new_v4();
+        let to = Uuid::new_v4();
+        banck
+            .add_account(Account::with_amount_and_id::<Dollar>("from", 100., &from))
+            .unwrap();
+        banck
+            .add_account(Account::with_id::<Dollar>("to", &to))
+            .unwrap();
+        banck
+            .set_spending_limit(
+                &from,
+                None,
+                Some(Limit {
+                    amount: 50.,
+                    enforcement: Enforcement::Block,
+                }),
+            )
+            .unwrap();
+
+        assert_eq!(banck.transfer(&from, &to, 40.), Ok(40.));
+        assert!(matches!(
+            banck.transfer(&from, &to, 20.),
+            Err(WithdrawalError::LimitExceeded(_))
+        ));
+        assert_eq!(banck.get_account_money(&from), Ok(60.));
+        assert_eq!(banck.get_account_money(&to), Ok(40.));
+    }
+
+    #[test]
+    fn revalue_at_today_rates() {
+        let mut banck = Bank::new();
+        let id = Uuid::new_v4();
+        banck
+            .add_account(Account::with_id::<Euro>("account", &id))
+            .unwrap();
+        banck.add_account_money(&id, 117.).unwrap();
+        banck.retrieve_account_money(&id, 11.7).unwrap();
+
+        let today = SystemTime::now();
+        let mut rates = RateTable::current(today);
+        rates.set("EUR", 1.5, today);
+
+        let revalued = banck.revalue(&id, Valuation::Today(&rates)).unwrap();
+        assert_eq!(revalued.len(), 2);
+        assert_eq!(revalued[0].target_amount, 117. / 1.5);
+        assert_eq!(revalued[0].rate_at, today);
+        assert!((revalued[1].target_amount - 15.).abs() < 1e-9);
+
+        // 90 euros bought at 1.17 dollars are now worth 1.5 dollars each
+        let gain = banck.unrealised_gain(&id, &rates).unwrap();
+        assert!((gain - 90. * (1.5 - 1.17)).abs() < 1e-9);
+        let no_change = banck
+            .unrealised_gain(&id, &RateTable::current(today))
+            .unwrap();
+        assert!(no_change.abs() < 1e-9);
+        assert!(banck.revalue(&Uuid::new_v4(), Valuation::Original).is_err());
+    }
 }
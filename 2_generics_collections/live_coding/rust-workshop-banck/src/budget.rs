@@ -0,0 +1,306 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt, time::SystemTime};
+
+use super::calendar::Month;
+
+/// What happens to a withdrawal which would go over a limit
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Enforcement {
+    /// The withdrawal is made, and a warning is recorded
+    Warn,
+    /// The withdrawal is refused
+    Block,
+}
+
+/// An amount of money which should not be spent in a month
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Limit {
+    /// The amount, in dollar
+    pub amount: f64,
+    pub enforcement: Enforcement,
+}
+
+/// A withdrawal counted against the limits of an account
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Spending {
+    pub at: SystemTime,
+    /// The category the withdrawal was tagged with, if any
+    pub category: Option<String>,
+    /// The amount withdrawn, in dollar
+    pub amount: f64,
+}
+
+/// A limit that a withdrawal would go over
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LimitExceeded {
+    /// The category of the limit, or `None` for the monthly budget
+    pub category: Option<String>,
+    pub month: Month,
+    pub limit: f64,
+    /// Amount already spent in the month, before the withdrawal
+    pub spent: f64,
+    /// Amount of the withdrawal
+    pub requested: f64,
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.category {
+            Some(category) => write!(f, "{} limit", category)?,
+            None => write!(f, "monthly budget")?,
+        }
+        write!(
+            f,
+            " of {} exceeded in {}: {} spent, {} requested",
+            self.limit, self.month, self.spent, self.requested
+        )
+    }
+}
+
+/// Spending against a limit during a month
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportLine {
+    /// The category of the limit, or `None` for the monthly budget
+    pub category: Option<String>,
+    pub limit: Limit,
+    pub spent: f64,
+}
+
+impl ReportLine {
+    /// Get what can still be spent, negative when the limit was exceeded
+    pub fn remaining(&self) -> f64 {
+        self.limit.amount - self.spent
+    }
+}
+
+/// The spending limits of an account, and the withdrawals counted against them
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Budget {
+    /// Limit on all the withdrawals of a month
+    monthly: Option<Limit>,
+    /// Limits on the withdrawals of a month tagged with a category
+    categories: BTreeMap<String, Limit>,
+    spending: Vec<Spending>,
+    /// Limits exceeded by withdrawals which were only warned about, oldest first
+    warnings: Vec<LimitExceeded>,
+}
+
+impl Budget {
+    /// Create a budget without any limit
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set, or remove with `None`, the limit of a category, or the monthly budget
+    /// when `category` is `None`.
+    pub fn set_limit(&mut self, category: Option<&str>, limit: Option<Limit>) {
+        match (category, limit) {
+            (None, limit) => self.monthly = limit,
+            (Some(category), Some(limit)) => {
+                self.categories.insert(category.to_string(), limit);
+            }
+            (Some(category), None) => {
+                self.categories.remove(category);
+            }
+        }
+    }
+
+    /// Get the limit of a category, or the monthly budget when `category` is `None`
+    pub fn limit(&self, category: Option<&str>) -> Option<&Limit> {
+        match category {
+            None => self.monthly.as_ref(),
+            Some(category) => self.categories.get(category),
+        }
+    }
+
+    /// Get every withdrawal counted against the limits, oldest first
+    pub fn spending(&self) -> &[Spending] {
+        &self.spending
+    }
+
+    /// Get the limits exceeded by withdrawals which were made anyway, oldest first
+    pub fn warnings(&self) -> &[LimitExceeded] {
+        &self.warnings
+    }
+
+    /// Count a withdrawal against the limits, unless a blocking limit would be
+    /// exceeded.
+    ///
+    /// # Arguments
+    /// * `amount` - the amount of the withdrawal, in dollar
+    /// * `category` - the category the withdrawal is tagged with, if any
+    /// * `now` - the time of the withdrawal, which decides its month
+    ///
+    /// # Returns
+    /// The limits exceeded which only warn, also added to `warnings`, or the first
+    /// blocking limit exceeded, in which case nothing is recorded.
+    pub fn authorize(
+        &mut self,
+        amount: f64,
+        category: Option<&str>,
+        now: SystemTime,
+    ) -> Result<Vec<LimitExceeded>, LimitExceeded> {
+        let month = Month::containing(now);
+        let mut limits = vec![(None, self.monthly)];
+        if let Some(category) = category {
+            limits.push((Some(category), self.categories.get(category).copied()));
+        }
+
+        let mut warnings = Vec::new();
+        for (limited, limit) in limits {
+            let limit = match limit {
+                Some(limit) => limit,
+                None => continue,
+            };
+            let spent = self.spent(limited, month);
+            if spent + amount <= limit.amount {
+                continue;
+            }
+
+            let exceeded = LimitExceeded {
+                category: limited.map(str::to_string),
+                month,
+                limit: limit.amount,
+                spent,
+                requested: amount,
+            };
+            match limit.enforcement {
+                Enforcement::Block => return Err(exceeded),
+                Enforcement::Warn => warnings.push(exceeded),
+            }
+        }
+
+        self.spending.push(Spending {
+            at: now,
+            category: category.map(str::to_string),
+            amount,
+        });
+        self.warnings.extend(warnings.iter().cloned());
+        Ok(warnings)
+    }
+
+    /// Get the amount spent during a month in a category, or in all categories when
+    /// `category` is `None`
+    pub fn spent(&self, category: Option<&str>, month: Month) -> f64 {
+        self.spending
+            .iter()
+            .filter(|spending| month.contains(spending.at))
+            .filter(|spending| category.is_none() || spending.category.as_deref() == category)
+            .map(|spending| spending.amount)
+            .sum()
+    }
+
+    /// Get the spending against every limit during a month, the monthly budget first
+    /// and then the categories in alphabetical order.
+    pub fn report(&self, month: Month) -> Vec<ReportLine> {
+        let monthly = self.monthly.map(|limit| (None, limit));
+        let categories = self
+            .categories
+            .iter()
+            .map(|(category, limit)| (Some(category.as_str()), *limit));
+
+        monthly
+            .into_iter()
+            .chain(categories)
+            .map(|(category, limit)| ReportLine {
+                category: category.map(str::to_string),
+                limit,
+                spent: self.spent(category, month),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::calendar::date;
+    use super::*;
+
+    fn limit(amount: f64, enforcement: Enforcement) -> Option<Limit> {
+        Some(Limit {
+            amount,
+            enforcement,
+        })
+    }
+
+    #[test]
+    fn block() {
+        let mut budget = Budget::new();
+        budget.set_limit(None, limit(100., Enforcement::Block));
+        let now = date(2021, 3, 14).unwrap();
+
+        assert_eq!(budget.authorize(60., None, now), Ok(vec![]));
+        let exceeded = budget.authorize(50., Some("food"), now).unwrap_err();
+        assert_eq!(exceeded.category, None);
+        assert_eq!(exceeded.spent, 60.);
+        assert_eq!(exceeded.requested, 50.);
+        assert_eq!(budget.spending().len(), 1);
+        assert_eq!(budget.authorize(40., Some("food"), now), Ok(vec![]));
+    }
+
+    #[test]
+    fn warn() {
+        let mut budget = Budget::new();
+        budget.set_limit(Some("food"), limit(50., Enforcement::Warn));
+        let now = date(2021, 3, 14).unwrap();
+
+        assert_eq!(budget.authorize(60., Some("rent"), now), Ok(vec![]));
+        let warnings = budget.authorize(60., Some("food"), now).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].category.as_deref(), Some("food"));
+        assert_eq!(warnings[0].spent, 0.);
+        assert_eq!(budget.warnings(), &warnings[..]);
+        assert_eq!(budget.spent(Some("food"), Month::containing(now)), 60.);
+    }
+
+    #[test]
+    fn months() {
+        let mut budget = Budget::new();
+        budget.set_limit(None, limit(100., Enforcement::Block));
+
+        let march = date(2021, 3, 31).unwrap();
+        let april = date(2021, 4, 1).unwrap();
+        budget.authorize(100., None, march).unwrap();
+        assert!(budget.authorize(1., None, march).is_err());
+        assert_eq!(budget.authorize(100., None, april), Ok(vec![]));
+    }
+
+    #[test]
+    fn remove_limit() {
+        let mut budget = Budget::new();
+        budget.set_limit(Some("food"), limit(10., Enforcement::Block));
+        budget.set_limit(Some("food"), None);
+
+        assert!(budget.limit(Some("food")).is_none());
+        assert!(budget
+            .authorize(20., Some("food"), SystemTime::UNIX_EPOCH)
+            .is_ok());
+    }
+
+    #[test]
+    fn report() {
+        let mut budget = Budget::new();
+        budget.set_limit(None, limit(500., Enforcement::Warn));
+        budget.set_limit(Some("rent"), limit(400., Enforcement::Block));
+        budget.set_limit(Some("food"), limit(100., Enforcement::Warn));
+
+        let march = date(2021, 3, 1).unwrap();
+        budget.authorize(400., Some("rent"), march).unwrap();
+        budget.authorize(120., Some("food"), march).unwrap();
+        budget
+            .authorize(30., None, date(2021, 4, 1).unwrap())
+            .unwrap();
+
+        let report = budget.report(Month::new(2021, 3).unwrap());
+        let categories: Vec<_> = report.iter().map(|line| line.category.as_deref()).collect();
+        assert_eq!(categories, vec![None, Some("food"), Some("rent")]);
+        assert_eq!(report[0].spent, 520.);
+        assert_eq!(report[0].remaining(), -20.);
+        assert_eq!(report[1].spent, 120.);
+        assert_eq!(report[2].remaining(), 0.);
+
+        let april = budget.report(Month::new(2021, 4).unwrap());
+        assert_eq!(april[0].spent, 30.);
+        assert_eq!(april[1].spent, 0.);
+    }
+}
@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    time::{Duration, SystemTime},
+};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Get the time of midnight UTC of a date of the Gregorian calendar
+///
+/// # Returns
+/// The time, or `None` if the date does not exist.
+pub fn date(year: i64, month: u32, day: u32) -> Option<SystemTime> {
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+    from_days(days_from_civil(year, month, day))
+}
+
+/// A month of the Gregorian calendar, in UTC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Month {
+    pub year: i64,
+    /// From 1 for January to 12 for December
+    pub month: u32,
+}
+
+impl Month {
+    /// Create a month, or `None` if `month` is not between 1 and 12
+    pub fn new(year: i64, month: u32) -> Option<Self> {
+        if (1..=12).contains(&month) {
+            Some(Self { year, month })
+        } else {
+            None
+        }
+    }
+
+    /// Get the month a given time is in
+    pub fn containing(time: SystemTime) -> Self {
+        let (year, month, _) = civil_from_days(days(time));
+        Self { year, month }
+    }
+
+    /// Get the month following this one
+    pub fn next(&self) -> Self {
+        self.after(1)
+    }
+
+    /// Get the month a number of months after this one
+    pub fn after(&self, months: u32) -> Self {
+        let months = self.year * 12 + (self.month - 1) as i64 + months as i64;
+        Self {
+            year: months.div_euclid(12),
+            month: months.rem_euclid(12) as u32 + 1,
+        }
+    }
+
+    /// Get the time this month starts at
+    pub fn start(&self) -> Option<SystemTime> {
+        date(self.year, self.month, 1)
+    }
+
+    /// Whether a time is in this month
+    pub fn contains(&self, time: SystemTime) -> bool {
+        Self::containing(time) == *self
+    }
+}
+
+impl fmt::Display for Month {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}", self.year, self.month)
+    }
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Number of whole days between 1970-01-01 and a time, negative before
+fn days(time: SystemTime) -> i64 {
+    match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(since) => (since.as_secs() / SECONDS_PER_DAY) as i64,
+        Err(before) => {
+            let before = before.duration();
+            let days = before.as_secs() / SECONDS_PER_DAY;
+            let partial = before > Duration::from_secs(days * SECONDS_PER_DAY);
+            -(days as i64) - partial as i64
+        }
+    }
+}
+
+/// Midnight UTC, a number of days after 1970-01-01
+fn from_days(days: i64) -> Option<SystemTime> {
+    let seconds = Duration::from_secs(days.unsigned_abs() * SECONDS_PER_DAY);
+    if days >= 0 {
+        SystemTime::UNIX_EPOCH.checked_add(seconds)
+    } else {
+        SystemTime::UNIX_EPOCH.checked_sub(seconds)
+    }
+}
+
+// Both conversions count years from March, so that the leap day is the last day of
+// the year, and in eras of 400 years, after which the calendar repeats.
+
+/// Number of days between 1970-01-01 and a date of the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_from_march = (month as i64 + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Date of the proleptic Gregorian calendar a number of days after 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u32;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    } as u32;
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(days: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(days * SECONDS_PER_DAY)
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(date(1970, 1, 1), Some(SystemTime::UNIX_EPOCH));
+        assert_eq!(date(2000, 3, 1), Some(day(11_017)));
+        assert_eq!(date(2024, 2, 29), Some(day(19_782)));
+        assert_eq!(date(2023, 2, 29), None);
+        assert_eq!(date(2023, 13, 1), None);
+        assert_eq!(
+            date(1969, 12, 31),
+            SystemTime::UNIX_EPOCH.checked_sub(Duration::from_secs(SECONDS_PER_DAY))
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        for days in -800_000..800_000 {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+
+    #[test]
+    fn months() {
+        let march = Month::new(2024, 3).unwrap();
+        assert_eq!(Month::containing(day(19_782)), Month::new(2024, 2).unwrap());
+        assert_eq!(Month::containing(day(19_783)), march);
+        assert_eq!(march.start(), Some(day(19_783)));
+        assert!(march.contains(day(19_783) + Duration::from_secs(10)));
+        assert_eq!(
+            Month::new(2024, 12).unwrap().next(),
+            Month::new(2025, 1).unwrap()
+        );
+        assert_eq!(march.after(22), Month::new(2026, 1).unwrap());
+        assert_eq!(march.after(0), march);
+        assert_eq!(march.to_string(), "2024-03");
+        assert!(Month::new(2024, 0).is_none());
+
+        let before_epoch = SystemTime::UNIX_EPOCH - Duration::from_secs(1);
+        assert_eq!(
+            Month::containing(before_epoch),
+            Month::new(1969, 12).unwrap()
+        );
+    }
+}
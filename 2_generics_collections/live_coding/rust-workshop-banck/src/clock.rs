@@ -0,0 +1,68 @@
+use std::{
+    cell::Cell,
+    rc::Rc,
+    time::{Duration, SystemTime},
+};
+
+/// Source of the current time, so that it can be controlled in tests or when
+/// replaying past operations
+pub trait Clock {
+    fn now(&self) -> SystemTime;
+}
+
+/// The clock of the system
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A clock which only moves when told to. Every clone shares the same time.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Rc<Cell<SystemTime>>,
+}
+
+impl ManualClock {
+    pub fn new(now: SystemTime) -> Self {
+        Self {
+            now: Rc::new(Cell::new(now)),
+        }
+    }
+
+    pub fn set(&self, now: SystemTime) {
+        self.now.set(now);
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.now.set(self.now.get() + by);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> SystemTime {
+        self.now.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manual_clock_is_shared() {
+        let clock = ManualClock::new(SystemTime::UNIX_EPOCH);
+        let other = clock.clone();
+
+        other.advance(Duration::from_secs(10));
+        assert_eq!(
+            clock.now(),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(10)
+        );
+        clock.set(SystemTime::UNIX_EPOCH);
+        assert_eq!(other.now(), SystemTime::UNIX_EPOCH);
+    }
+}
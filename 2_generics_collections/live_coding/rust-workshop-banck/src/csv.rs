@@ -0,0 +1,27 @@
+use std::{fmt, io};
+
+/// Error of a data table read from a CSV file
+#[derive(Debug)]
+pub enum CsvError {
+    Io(io::Error),
+    /// A line could not be read, numbered from 1
+    Line {
+        line: usize,
+        reason: String,
+    },
+}
+
+impl From<io::Error> for CsvError {
+    fn from(error: io::Error) -> Self {
+        CsvError::Io(error)
+    }
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvError::Io(error) => write!(f, "cannot read csv: {}", error),
+            CsvError::Line { line, reason } => write!(f, "line {}: {}", line, reason),
+        }
+    }
+}
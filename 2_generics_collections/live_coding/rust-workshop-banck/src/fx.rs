@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, time::SystemTime};
+
+use super::money::standard;
+
+/// A conversion of money from one currency into another, as it happened
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Conversion {
+    /// ISO 4217 code of the currency given
+    pub source: String,
+    /// ISO 4217 code of the currency received
+    pub target: String,
+    pub source_amount: f64,
+    pub target_amount: f64,
+    /// Amount of `target` received for one unit of `source`
+    pub rate: f64,
+    /// Time at which `rate` was quoted
+    pub rate_at: SystemTime,
+}
+
+impl Conversion {
+    /// Get the same conversion made at another rate
+    pub fn at_rate(&self, rate: f64, rate_at: SystemTime) -> Self {
+        Self {
+            target_amount: self.source_amount * rate,
+            rate,
+            rate_at,
+            ..self.clone()
+        }
+    }
+}
+
+/// The dollar value of one unit of a currency, as quoted at a given time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rate {
+    pub dollars: f64,
+    pub at: SystemTime,
+}
+
+/// A set of exchange rates, indexed by currency code
+#[derive(Debug, Clone, Default)]
+pub struct RateTable {
+    rates: HashMap<String, Rate>,
+}
+
+impl RateTable {
+    /// Create a table without any rate
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a table holding the rates of every currency of the `money` module,
+    /// quoted at `at`
+    pub fn current(at: SystemTime) -> Self {
+        let mut table = Self::new();
+        for currency in standard().iter() {
+            table.set(&currency.code, currency.rate, at);
+        }
+        table
+    }
+
+    /// Set the dollar value of one unit of `currency`
+    pub fn set(&mut self, currency: &str, dollars: f64, at: SystemTime) {
+        self.rates
+            .insert(currency.to_string(), Rate { dollars, at });
+    }
+
+    pub fn get(&self, currency: &str) -> Option<&Rate> {
+        self.rates.get(currency)
+    }
+
+    /// Iterate over the rates of the table, by currency code
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Rate)> {
+        self.rates
+            .iter()
+            .map(|(currency, rate)| (currency.as_str(), rate))
+    }
+
+    /// Get the amount of `target` one unit of `source` is worth, and the time of the
+    /// oldest of the two quotes used.
+    pub fn rate(&self, source: &str, target: &str) -> Option<(f64, SystemTime)> {
+        let source = self.get(source)?;
+        let target = self.get(target)?;
+        Some((source.dollars / target.dollars, source.at.min(target.at)))
+    }
+
+    /// Get the conversion as it would be made with the rates of this table, or the
+    /// conversion itself if one of its currencies is missing.
+    pub fn revalue(&self, conversion: &Conversion) -> Conversion {
+        match self.rate(&conversion.source, &conversion.target) {
+            Some((rate, at)) => conversion.at_rate(rate, at),
+            None => conversion.clone(),
+        }
+    }
+}
+
+/// The rates to value past conversions with
+#[derive(Debug, Clone, Copy)]
+pub enum Valuation<'r> {
+    /// The rates used when the conversions were made
+    Original,
+    /// The given rates
+    Today(&'r RateTable),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn cross_rate() {
+        let at = SystemTime::UNIX_EPOCH;
+        let mut rates = RateTable::new();
+        rates.set("EUR", 1.2, at);
+        rates.set("USD", 1., at + Duration::from_secs(1));
+
+        assert_eq!(rates.rate("EUR", "USD"), Some((1.2, at)));
+        assert_eq!(rates.rate("USD", "EUR").unwrap().0, 1. / 1.2);
+        assert!(rates.rate("EUR", "MRU").is_none());
+    }
+
+    #[test]
+    fn current_rates() {
+        let rates = RateTable::current(SystemTime::UNIX_EPOCH);
+
+        assert_eq!(rates.get("USD").unwrap().dollars, 1.);
+        assert_eq!(rates.get("EUR").unwrap().dollars, 1.17);
+        assert_eq!(rates.get("MRU").unwrap().dollars, 0.03);
+    }
+
+    #[test]
+    fn revalue() {
+        let at = SystemTime::UNIX_EPOCH;
+        let conversion = Conversion {
+            source: "USD".to_string(),
+            target: "EUR".to_string(),
+            source_amount: 120.,
+            target_amount: 100.,
+            rate: 1. / 1.2,
+            rate_at: at,
+        };
+        let mut rates = RateTable::new();
+        rates.set("USD", 1., at);
+        rates.set("EUR", 1.5, at);
+
+        let revalued = rates.revalue(&conversion);
+        assert_eq!(revalued.target_amount, 80.);
+        assert_eq!(revalued.source_amount, 120.);
+        assert_eq!(RateTable::new().revalue(&conversion), conversion);
+    }
+}
@@ -0,0 +1,377 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    io::BufRead,
+    ops::Bound,
+    time::{Duration, SystemTime},
+};
+
+use super::bank::{MovementError, NoSuchAccountError};
+use super::calendar::date;
+use super::csv::CsvError;
+use super::fx::{Rate, RateTable};
+use super::ledger::LedgerError;
+use super::money::Dollar;
+
+/// How to find the rate of a currency at a time no rate was recorded for
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Lookup {
+    /// Only a rate recorded at that very time is used
+    Exact,
+    /// The last rate recorded at or before that time is used
+    Previous,
+    /// The rate is interpolated linearly between the rates recorded just before and
+    /// just after that time
+    Interpolated,
+}
+
+/// Dollar values of currencies over time
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+// Times cannot be keys of a JSON object, so the history is written as a list of rates
+#[serde(
+    from = "Vec<(String, SystemTime, f64)>",
+    into = "Vec<(String, SystemTime, f64)>"
+)]
+pub struct RateHistory {
+    series: HashMap<String, BTreeMap<SystemTime, f64>>,
+}
+
+impl From<Vec<(String, SystemTime, f64)>> for RateHistory {
+    fn from(rates: Vec<(String, SystemTime, f64)>) -> Self {
+        let mut history = Self::new();
+        for (currency, at, dollars) in rates {
+            history.insert(&currency, at, dollars);
+        }
+        history
+    }
+}
+
+impl From<RateHistory> for Vec<(String, SystemTime, f64)> {
+    fn from(history: RateHistory) -> Self {
+        let mut rates = Vec::new();
+        for (currency, series) in history.series {
+            for (at, dollars) in series {
+                rates.push((currency.clone(), at, dollars));
+            }
+        }
+        rates
+    }
+}
+
+/// Error of a conversion made at the rate of a given time
+#[derive(Debug, Clone, PartialEq)]
+pub enum RateError {
+    NoSuchAccount(NoSuchAccountError),
+    /// No rate of the currency is known for that time
+    NoRate {
+        currency: String,
+        as_of: SystemTime,
+    },
+    /// The ledger of the banck refused to record the conversion
+    Ledger(LedgerError),
+}
+
+impl From<NoSuchAccountError> for RateError {
+    fn from(error: NoSuchAccountError) -> Self {
+        RateError::NoSuchAccount(error)
+    }
+}
+
+impl From<MovementError> for RateError {
+    fn from(error: MovementError) -> Self {
+        match error {
+            MovementError::NoSuchAccount(error) => RateError::NoSuchAccount(error),
+            MovementError::Ledger(error) => RateError::Ledger(error),
+        }
+    }
+}
+
+impl fmt::Display for RateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RateError::NoSuchAccount(error) => write!(f, "{}", error),
+            RateError::Ledger(error) => write!(f, "{}", error),
+            RateError::NoRate { currency, as_of } => write!(
+                f,
+                "no rate of {} known at {} seconds since the epoch",
+                currency,
+                seconds(*as_of)
+            ),
+        }
+    }
+}
+
+impl RateHistory {
+    /// Create a history without any rate
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the dollar value of one unit of `currency` at a given time, replacing
+    /// the rate already recorded at that time if any.
+    pub fn insert(&mut self, currency: &str, at: SystemTime, dollars: f64) {
+        self.series
+            .entry(currency.to_string())
+            .or_default()
+            .insert(at, dollars);
+    }
+
+    /// Get the number of rates recorded, all currencies included
+    pub fn len(&self) -> usize {
+        self.series.values().map(BTreeMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the dollar value of one unit of `currency` at a given time.
+    /// The dollar is always worth one dollar, whatever was recorded.
+    ///
+    /// # Returns
+    /// The rate and the time it was recorded at, or `as_of` for an interpolated
+    /// rate. `None` if `lookup` finds no rate.
+    pub fn rate(&self, currency: &str, as_of: SystemTime, lookup: Lookup) -> Option<Rate> {
+        if currency == Dollar::CODE {
+            return Some(Rate {
+                dollars: 1.,
+                at: as_of,
+            });
+        }
+
+        let series = self.series.get(currency)?;
+        let previous = series.range(..=as_of).next_back();
+        let rate = |(at, dollars): (&SystemTime, &f64)| Rate {
+            dollars: *dollars,
+            at: *at,
+        };
+
+        match lookup {
+            Lookup::Exact => series
+                .get(&as_of)
+                .map(|&dollars| Rate { dollars, at: as_of }),
+            Lookup::Previous => previous.map(rate),
+            Lookup::Interpolated => {
+                let (before, before_dollars) = previous?;
+                if *before == as_of {
+                    return Some(rate((before, before_dollars)));
+                }
+                let (after, after_dollars) = series
+                    .range((Bound::Excluded(as_of), Bound::Unbounded))
+                    .next()?;
+
+                let elapsed = seconds(as_of) - seconds(*before);
+                let span = seconds(*after) - seconds(*before);
+                Some(Rate {
+                    dollars: before_dollars + (after_dollars - before_dollars) * elapsed / span,
+                    at: as_of,
+                })
+            }
+        }
+    }
+
+    /// Get the rates of every currency of the history at a given time, leaving out
+    /// the currencies `lookup` finds no rate for.
+    pub fn table(&self, as_of: SystemTime, lookup: Lookup) -> RateTable {
+        let mut table = RateTable::new();
+        for currency in self.series.keys() {
+            if let Some(rate) = self.rate(currency, as_of, lookup) {
+                table.set(currency, rate.dollars, rate.at);
+            }
+        }
+        table
+    }
+
+    /// Record rates read from CSV, one per line: `currency,time,dollars`.
+    /// The time is either a date, `2021-03-14`, taken at midnight UTC, or a number of
+    /// seconds since the Unix epoch. A first line starting with `currency` is taken as
+    /// a header, and empty lines are skipped.
+    ///
+    /// # Returns
+    /// The number of rates recorded. Nothing is recorded if a line is invalid.
+    pub fn load_csv<R: BufRead>(&mut self, reader: R) -> Result<usize, CsvError> {
+        let mut rates = Vec::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || (i == 0 && line.starts_with("currency")) {
+                continue;
+            }
+            let error = |reason: &str| CsvError::Line {
+                line: i + 1,
+                reason: reason.to_string(),
+            };
+
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            if fields.len() != 3 {
+                return Err(error("expected currency, time and dollars"));
+            }
+            if fields[0].is_empty() {
+                return Err(error("missing currency"));
+            }
+            let at = parse_time(fields[1]).ok_or_else(|| error("invalid time"))?;
+            let dollars: f64 = fields[2].parse().map_err(|_| error("invalid rate"))?;
+            if !(dollars.is_finite() && dollars > 0.) {
+                return Err(error("rates must be positive"));
+            }
+            rates.push((fields[0].to_string(), at, dollars));
+        }
+
+        for (currency, at, dollars) in &rates {
+            self.insert(currency, *at, *dollars);
+        }
+        Ok(rates.len())
+    }
+}
+
+fn seconds(time: SystemTime) -> f64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|duration| duration.as_secs_f64())
+        .unwrap_or_else(|before| -before.duration().as_secs_f64())
+}
+
+/// Read a date, `YYYY-MM-DD`, or a number of seconds since the Unix epoch
+fn parse_time(field: &str) -> Option<SystemTime> {
+    if !field.is_empty() && field.bytes().all(|b| b.is_ascii_digit()) {
+        let seconds = Duration::from_secs(field.parse().ok()?);
+        // Adding seconds too far in the future panics, checking them does not
+        return SystemTime::UNIX_EPOCH.checked_add(seconds);
+    }
+
+    let mut parts = field.splitn(3, '-');
+    let year = parts.next()?.parse().ok()?;
+    let month = parts.next()?.parse().ok()?;
+    let day = parts.next()?.parse().ok()?;
+    date(year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(days: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(days * 24 * 60 * 60)
+    }
+
+    fn history() -> RateHistory {
+        let mut history = RateHistory::new();
+        history.insert("EUR", day(10), 1.1);
+        history.insert("EUR", day(20), 1.2);
+        history
+    }
+
+    #[test]
+    fn exact() {
+        let history = history();
+        assert_eq!(
+            history.rate("EUR", day(10), Lookup::Exact),
+            Some(Rate {
+                dollars: 1.1,
+                at: day(10)
+            })
+        );
+        assert!(history.rate("EUR", day(11), Lookup::Exact).is_none());
+        assert!(history.rate("MRU", day(10), Lookup::Exact).is_none());
+        assert_eq!(
+            history.rate("USD", day(3), Lookup::Exact).unwrap().dollars,
+            1.
+        );
+    }
+
+    #[test]
+    fn previous() {
+        let history = history();
+        assert!(history.rate("EUR", day(9), Lookup::Previous).is_none());
+        assert_eq!(
+            history.rate("EUR", day(15), Lookup::Previous),
+            Some(Rate {
+                dollars: 1.1,
+                at: day(10)
+            })
+        );
+        assert_eq!(
+            history.rate("EUR", day(30), Lookup::Previous).unwrap().at,
+            day(20)
+        );
+    }
+
+    #[test]
+    fn interpolated() {
+        let history = history();
+        let rate = history.rate("EUR", day(15), Lookup::Interpolated).unwrap();
+        assert!((rate.dollars - 1.15).abs() < 1e-12);
+        assert_eq!(rate.at, day(15));
+        assert_eq!(
+            history
+                .rate("EUR", day(20), Lookup::Interpolated)
+                .unwrap()
+                .dollars,
+            1.2
+        );
+        assert!(history.rate("EUR", day(9), Lookup::Interpolated).is_none());
+        assert!(history.rate("EUR", day(21), Lookup::Interpolated).is_none());
+    }
+
+    #[test]
+    fn table() {
+        let mut history = history();
+        history.insert("MRU", day(18), 0.03);
+
+        let table = history.table(day(15), Lookup::Previous);
+        assert_eq!(table.get("EUR").unwrap().dollars, 1.1);
+        assert!(table.get("MRU").is_none());
+    }
+
+    #[test]
+    fn load_csv() {
+        let csv = "currency,time,dollars\n\
+                   EUR,1970-01-11,1.1\n\
+                   \n\
+                   EUR, 1728000 , 1.2\n\
+                   MRU,2021-03-14,0.025\n";
+        let mut history = RateHistory::new();
+
+        assert_eq!(history.load_csv(csv.as_bytes()).unwrap(), 3);
+        assert_eq!(history.len(), 3);
+        assert_eq!(
+            history.rate("EUR", day(20), Lookup::Exact).unwrap().dollars,
+            1.2
+        );
+        assert_eq!(
+            history.rate("EUR", day(10), Lookup::Exact).unwrap().dollars,
+            1.1
+        );
+        assert!(history.rate("MRU", day(18_700), Lookup::Exact).is_some());
+    }
+
+    #[test]
+    fn load_invalid_csv() {
+        let mut history = RateHistory::new();
+        let line = |csv: &str| match history.clone().load_csv(csv.as_bytes()) {
+            Err(CsvError::Line { line, .. }) => line,
+            other => panic!("unexpected result: {:?}", other),
+        };
+
+        assert_eq!(line("EUR,1970-01-11,1.1\nEUR,1970-02-30,1.1"), 2);
+        assert_eq!(line("EUR,yesterday,1.1"), 1);
+        assert_eq!(line("EUR,1970-01-11"), 1);
+        assert_eq!(line("EUR,1970-01-11,-1"), 1);
+        assert_eq!(line("currency,time,dollars\n,1970-01-11,1"), 2);
+        assert_eq!(line("EUR,18446744073709551615,1.1"), 1);
+
+        assert!(history
+            .load_csv("EUR,1970-01-11,1.1\nEUR,0,x".as_bytes())
+            .is_err());
+        assert!(history.is_empty());
+    }
+
+    #[test]
+    fn times() {
+        assert_eq!(parse_time("2000-03-01"), Some(day(11_017)));
+        assert_eq!(parse_time("864000"), Some(day(10)));
+        assert_eq!(parse_time("2023-02-29"), None);
+        assert_eq!(parse_time("2023-02"), None);
+        assert_eq!(parse_time(""), None);
+    }
+}
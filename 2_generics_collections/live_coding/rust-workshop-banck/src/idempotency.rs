@@ -0,0 +1,205 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt,
+    time::{Duration, SystemTime},
+};
+use uuid::Uuid;
+
+use super::bank::{MovementError, NoSuchAccountError, WithdrawalError};
+use super::budget::LimitExceeded;
+use super::ledger::LedgerError;
+
+/// Default amount of time during which a request id is remembered
+pub const DEFAULT_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A mutating operation on the bank, with the arguments it was requested with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Request {
+    Deposit { account: Uuid, amount: f64 },
+    Withdrawal { account: Uuid, amount: f64 },
+    Transfer { from: Uuid, to: Uuid, amount: f64 },
+}
+
+/// The result of a mutating operation on the bank, as first returned to the client.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Outcome {
+    Deposit(Result<(), MovementError>),
+    Withdrawal(Result<f64, WithdrawalError>),
+    Transfer(Result<f64, WithdrawalError>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RequestError {
+    /// The operation itself failed because of a missing account
+    NoSuchAccount(NoSuchAccountError),
+    /// The withdrawal was refused by a spending limit of the account
+    LimitExceeded(LimitExceeded),
+    /// The request id was already used for another operation, or with other arguments
+    ReusedRequestId(Uuid),
+    /// The ledger of the banck refused to record the operation
+    Ledger(LedgerError),
+}
+
+impl From<NoSuchAccountError> for RequestError {
+    fn from(err: NoSuchAccountError) -> Self {
+        RequestError::NoSuchAccount(err)
+    }
+}
+
+impl From<MovementError> for RequestError {
+    fn from(err: MovementError) -> Self {
+        match err {
+            MovementError::NoSuchAccount(err) => RequestError::NoSuchAccount(err),
+            MovementError::Ledger(err) => RequestError::Ledger(err),
+        }
+    }
+}
+
+impl From<WithdrawalError> for RequestError {
+    fn from(err: WithdrawalError) -> Self {
+        match err {
+            WithdrawalError::NoSuchAccount(err) => RequestError::NoSuchAccount(err),
+            WithdrawalError::LimitExceeded(exceeded) => RequestError::LimitExceeded(exceeded),
+            WithdrawalError::Ledger(err) => RequestError::Ledger(err),
+        }
+    }
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestError::NoSuchAccount(err) => err.fmt(f),
+            RequestError::LimitExceeded(exceeded) => exceeded.fmt(f),
+            RequestError::ReusedRequestId(id) => {
+                write!(f, "request id already used for another operation: {}", id)
+            }
+            RequestError::Ledger(err) => err.fmt(f),
+        }
+    }
+}
+
+/// A remembered outcome, along with the request which produced it and the time at
+/// which it was first produced
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    pub request: Request,
+    pub outcome: Outcome,
+    pub recorded_at: SystemTime,
+}
+
+/// Remember the outcome of client requests for a given window, so that a retried
+/// request is answered with its original result instead of being applied twice.
+#[derive(Debug, Clone)]
+pub struct RequestCache {
+    window: Duration,
+    records: HashMap<Uuid, Record>,
+}
+
+impl RequestCache {
+    /// Create an empty cache remembering requests for `window`
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            records: HashMap::new(),
+        }
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    pub fn set_window(&mut self, window: Duration) {
+        self.window = window;
+    }
+
+    /// Get a previous request and its outcome, if it is still within the window at
+    /// `now`. Expired records are forgotten along the way.
+    pub fn get(&mut self, request_id: &Uuid, now: SystemTime) -> Option<&Record> {
+        self.forget_expired(now);
+        self.records.get(request_id)
+    }
+
+    /// Remember the outcome of a request produced at `now`
+    pub fn insert(
+        &mut self,
+        request_id: &Uuid,
+        request: Request,
+        outcome: Outcome,
+        now: SystemTime,
+    ) {
+        self.insert_record(
+            request_id,
+            Record {
+                request,
+                outcome,
+                recorded_at: now,
+            },
+        );
+    }
+
+    /// Remember an already timestamped record, for example when restoring a bank
+    pub fn insert_record(&mut self, request_id: &Uuid, record: Record) {
+        self.records.insert(*request_id, record);
+    }
+
+    /// Iterate over every remembered request
+    pub fn records(&self) -> impl Iterator<Item = (&Uuid, &Record)> {
+        self.records.iter()
+    }
+
+    fn forget_expired(&mut self, now: SystemTime) {
+        let window = self.window;
+
+        self.records.retain(|_, record| {
+            now.duration_since(record.recorded_at)
+                .map(|age| age < window)
+                // The record is in the future: the clock went back, keep it
+                .unwrap_or(true)
+        });
+    }
+}
+
+impl Default for RequestCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOW)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deposit() -> Request {
+        Request::Deposit {
+            account: Uuid::new_v4(),
+            amount: 10.,
+        }
+    }
+
+    #[test]
+    fn remembered() {
+        let mut cache = RequestCache::default();
+        let id = Uuid::new_v4();
+        let now = SystemTime::now();
+        let request = deposit();
+        cache.insert(&id, request.clone(), Outcome::Deposit(Ok(())), now);
+
+        let record = cache.get(&id, now).unwrap();
+        assert_eq!(record.request, request);
+        assert_eq!(record.outcome, Outcome::Deposit(Ok(())));
+        assert!(cache.get(&Uuid::new_v4(), now).is_none());
+    }
+
+    #[test]
+    fn expired() {
+        let mut cache = RequestCache::new(Duration::from_secs(60));
+        let id = Uuid::new_v4();
+        let now = SystemTime::now();
+        cache.insert(&id, deposit(), Outcome::Deposit(Ok(())), now);
+
+        assert!(cache.get(&id, now + Duration::from_secs(30)).is_some());
+        assert!(cache.get(&id, now + Duration::from_secs(120)).is_none());
+        assert_eq!(cache.records().count(), 0);
+    }
+}
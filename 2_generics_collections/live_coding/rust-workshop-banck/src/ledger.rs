@@ -0,0 +1,475 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::TryFrom,
+    fmt,
+};
+use uuid::Uuid;
+
+use super::account::Account;
+use super::money::standard;
+
+/// Code of the cash held by the banck
+pub const CASH: &str = "1000";
+/// Code prefix of the deposits of customers, followed by the id of their account
+pub const CUSTOMER_PREFIX: &str = "2000-";
+/// Code of the capital of the banck
+pub const EQUITY: &str = "3000";
+/// Code of the account absorbing currency conversions and their rounding
+pub const EXCHANGE: &str = "3900";
+/// Code of the fees charged to customers
+pub const FEES: &str = "4000";
+
+/// The currency in which the banck receives and gives cash
+pub const CASH_CURRENCY: &str = "USD";
+
+/// Convert an amount of money into an integer amount of minor units of its currency,
+/// such as cents, as stored in the ledger. A currency missing from the registry is
+/// counted in hundredths.
+pub fn to_minor(amount: f64, currency: &str) -> i64 {
+    let minor_units = standard()
+        .get(currency)
+        .map_or(2, |currency| currency.minor_units);
+    (amount * 10f64.powi(minor_units as i32)).round() as i64
+}
+
+/// Get the code of the ledger account holding the deposits of a customer account
+pub fn customer_code(account_id: &Uuid) -> String {
+    format!("{}{}", CUSTOMER_PREFIX, account_id)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Kind {
+    Asset,
+    Liability,
+    Equity,
+    Income,
+    Expense,
+}
+
+/// An account of the chart of accounts. This is the banck's view of where money is,
+/// not a customer account.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedgerAccount {
+    pub code: String,
+    pub name: String,
+    pub kind: Kind,
+}
+
+/// The list of ledger accounts postings can be made to, indexed by code
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChartOfAccounts {
+    accounts: BTreeMap<String, LedgerAccount>,
+}
+
+impl ChartOfAccounts {
+    /// Create an empty chart of accounts
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a chart of accounts holding the accounts owned by the banck:
+    /// cash, equity, currency exchange and fees.
+    pub fn standard() -> Self {
+        let mut chart = Self::new();
+        chart.open(CASH, "Cash", Kind::Asset);
+        chart.open(EQUITY, "Equity", Kind::Equity);
+        chart.open(EXCHANGE, "Currency exchange", Kind::Equity);
+        chart.open(FEES, "Fees", Kind::Income);
+        chart
+    }
+
+    /// Return true if the account was opened successfully, false if the code
+    /// was already used
+    pub fn open(&mut self, code: &str, name: &str, kind: Kind) -> bool {
+        if self.accounts.contains_key(code) {
+            return false;
+        }
+        self.accounts.insert(
+            code.to_string(),
+            LedgerAccount {
+                code: code.to_string(),
+                name: name.to_string(),
+                kind,
+            },
+        );
+        true
+    }
+
+    pub fn get(&self, code: &str) -> Option<&LedgerAccount> {
+        self.accounts.get(code)
+    }
+
+    /// Iterate over every account, ordered by code
+    pub fn iter(&self) -> impl Iterator<Item = &LedgerAccount> {
+        self.accounts.values()
+    }
+}
+
+/// One side of a posting. A positive amount is a debit, a negative amount a credit.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Leg {
+    pub account: String,
+    pub currency: String,
+    /// Amount in minor units of `currency`
+    pub amount: i64,
+}
+
+impl Leg {
+    pub fn debit(account: &str, currency: &str, amount: i64) -> Self {
+        Self {
+            account: account.to_string(),
+            currency: currency.to_string(),
+            amount,
+        }
+    }
+
+    pub fn credit(account: &str, currency: &str, amount: i64) -> Self {
+        Self::debit(account, currency, -amount)
+    }
+}
+
+/// A set of legs whose debits and credits balance, in every currency
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Posting {
+    pub description: String,
+    pub legs: Vec<Leg>,
+}
+
+impl Posting {
+    pub fn new(description: &str, legs: Vec<Leg>) -> Self {
+        Self {
+            description: description.to_string(),
+            legs,
+        }
+    }
+
+    /// Sum of the legs of this posting, per currency
+    pub fn totals(&self) -> BTreeMap<String, i64> {
+        let mut totals = BTreeMap::new();
+        for leg in &self.legs {
+            *totals.entry(leg.currency.clone()).or_insert(0) += leg.amount;
+        }
+        totals
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum LedgerError {
+    /// A leg refers to an account missing from the chart of accounts
+    UnknownAccount(String),
+    /// An account with this code is already in the chart of accounts
+    AccountExists(String),
+    /// Debits and credits differ by `difference` in `currency`
+    Unbalanced { currency: String, difference: i64 },
+}
+
+impl fmt::Display for LedgerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LedgerError::UnknownAccount(code) => write!(f, "no such ledger account: {}", code),
+            LedgerError::AccountExists(code) => {
+                write!(f, "ledger account already open: {}", code)
+            }
+            LedgerError::Unbalanced {
+                currency,
+                difference,
+            } => write!(
+                f,
+                "unbalanced by {} minor units of {}",
+                difference, currency
+            ),
+        }
+    }
+}
+
+/// A double-entry journal of every movement of money in the banck.
+///
+/// Only the chart of accounts and the postings are serialized: the balances are
+/// computed again, and checked, by posting them back when deserializing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "Journal")]
+pub struct Ledger {
+    chart: ChartOfAccounts,
+    postings: Vec<Posting>,
+    /// Balance of each ledger account, per currency
+    #[serde(skip)]
+    balances: HashMap<(String, String), i64>,
+}
+
+/// The serialized form of a ledger
+#[derive(Deserialize)]
+struct Journal {
+    chart: ChartOfAccounts,
+    postings: Vec<Posting>,
+}
+
+impl TryFrom<Journal> for Ledger {
+    type Error = LedgerError;
+
+    fn try_from(journal: Journal) -> Result<Self, LedgerError> {
+        let mut ledger = Ledger::new(journal.chart);
+        for posting in journal.postings {
+            ledger.post(posting)?;
+        }
+        Ok(ledger)
+    }
+}
+
+impl Ledger {
+    /// Create an empty ledger using the given chart of accounts
+    pub fn new(chart: ChartOfAccounts) -> Self {
+        Self {
+            chart,
+            postings: Vec::new(),
+            balances: HashMap::new(),
+        }
+    }
+
+    pub fn chart(&self) -> &ChartOfAccounts {
+        &self.chart
+    }
+
+    pub fn chart_mut(&mut self) -> &mut ChartOfAccounts {
+        &mut self.chart
+    }
+
+    /// Every posting made so far, oldest first
+    pub fn postings(&self) -> &[Posting] {
+        &self.postings
+    }
+
+    /// Get the balance of a ledger account in a given currency, in its minor units.
+    /// A positive balance is a debit balance.
+    pub fn balance(&self, code: &str, currency: &str) -> i64 {
+        self.balances
+            .get(&(code.to_string(), currency.to_string()))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Record a posting.
+    ///
+    /// # Returns
+    /// An error, and nothing recorded, if the posting uses an unknown account or
+    /// does not balance in one of its currencies.
+    pub fn post(&mut self, posting: Posting) -> Result<(), LedgerError> {
+        if let Some(leg) = posting
+            .legs
+            .iter()
+            .find(|leg| self.chart.get(&leg.account).is_none())
+        {
+            return Err(LedgerError::UnknownAccount(leg.account.clone()));
+        }
+        if let Some((currency, difference)) =
+            posting.totals().into_iter().find(|(_, total)| *total != 0)
+        {
+            return Err(LedgerError::Unbalanced {
+                currency,
+                difference,
+            });
+        }
+
+        for leg in &posting.legs {
+            *self
+                .balances
+                .entry((leg.account.clone(), leg.currency.clone()))
+                .or_insert(0) += leg.amount;
+        }
+        self.postings.push(posting);
+        Ok(())
+    }
+
+    /// Sum of the balances of every account, per currency. Every sum is zero
+    /// in a consistent ledger.
+    pub fn trial_balance(&self) -> BTreeMap<String, i64> {
+        let mut totals = BTreeMap::new();
+        for ((_, currency), balance) in &self.balances {
+            *totals.entry(currency.clone()).or_insert(0) += balance;
+        }
+        totals
+    }
+
+    /// Check that the trial balance sums to zero in every currency
+    pub fn check(&self) -> Result<(), LedgerError> {
+        match self
+            .trial_balance()
+            .into_iter()
+            .find(|(_, total)| *total != 0)
+        {
+            Some((currency, difference)) => Err(LedgerError::Unbalanced {
+                currency,
+                difference,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Open the ledger account of a customer account, crediting its current
+    /// amount against `counterpart`, in dollars.
+    ///
+    /// # Returns
+    /// An error, and nothing posted, if the customer account already has a ledger
+    /// account, or if the opening cannot be posted.
+    pub(crate) fn open_customer(
+        &mut self,
+        account: &Account,
+        counterpart: &str,
+    ) -> Result<(), LedgerError> {
+        let code = customer_code(account.get_id());
+        if !self.chart.open(&code, account.get_name(), Kind::Liability) {
+            return Err(LedgerError::AccountExists(code));
+        }
+        self.customer_movement(
+            "open account",
+            account,
+            0.,
+            counterpart,
+            account.get_value(),
+        )
+    }
+
+    /// Record that a customer account went from `before` to its current amount, in
+    /// its own currency, in exchange for `dollars` on the `counterpart` account.
+    /// The conversion, and its rounding, is absorbed by the exchange account.
+    pub(crate) fn customer_movement(
+        &mut self,
+        description: &str,
+        account: &Account,
+        before: f64,
+        counterpart: &str,
+        dollars: f64,
+    ) -> Result<(), LedgerError> {
+        let currency = account.get_currency();
+        let delta = to_minor(account.get_amount(), currency) - to_minor(before, currency);
+        let legs = vec![
+            Leg::credit(&customer_code(account.get_id()), currency, delta),
+            Leg::debit(counterpart, CASH_CURRENCY, to_minor(dollars, CASH_CURRENCY)),
+        ];
+        self.post_through_exchange(description, legs)
+    }
+
+    /// Record a transfer between two customer accounts, which went from `from_before`
+    /// and `to_before` to their current amount.
+    pub(crate) fn transfer(
+        &mut self,
+        from: &Account,
+        from_before: f64,
+        to: &Account,
+        to_before: f64,
+    ) -> Result<(), LedgerError> {
+        let legs = vec![
+            Leg::credit(
+                &customer_code(from.get_id()),
+                from.get_currency(),
+                to_minor(from.get_amount(), from.get_currency())
+                    - to_minor(from_before, from.get_currency()),
+            ),
+            Leg::credit(
+                &customer_code(to.get_id()),
+                to.get_currency(),
+                to_minor(to.get_amount(), to.get_currency())
+                    - to_minor(to_before, to.get_currency()),
+            ),
+        ];
+        self.post_through_exchange("transfer", legs)
+    }
+
+    /// Balance each currency of `legs` on the exchange account, then post them
+    fn post_through_exchange(
+        &mut self,
+        description: &str,
+        legs: Vec<Leg>,
+    ) -> Result<(), LedgerError> {
+        let mut posting = Posting::new(description, legs);
+        for (currency, total) in posting.totals() {
+            if total != 0 {
+                posting.legs.push(Leg::credit(EXCHANGE, &currency, total));
+            }
+        }
+        posting.legs.retain(|leg| leg.amount != 0);
+
+        self.post(posting)
+    }
+}
+
+impl Default for Ledger {
+    fn default() -> Self {
+        Self::new(ChartOfAccounts::standard())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn balanced_posting() {
+        let mut ledger = Ledger::default();
+        let posting = Posting::new(
+            "capital",
+            vec![
+                Leg::debit(CASH, "USD", 1000),
+                Leg::credit(EQUITY, "USD", 1000),
+            ],
+        );
+
+        assert!(ledger.post(posting).is_ok());
+        assert_eq!(ledger.balance(CASH, "USD"), 1000);
+        assert_eq!(ledger.balance(EQUITY, "USD"), -1000);
+        assert_eq!(ledger.postings().len(), 1);
+        assert!(ledger.check().is_ok());
+    }
+
+    #[test]
+    fn unbalanced_posting() {
+        let mut ledger = Ledger::default();
+        let posting = Posting::new(
+            "broken",
+            vec![
+                Leg::debit(CASH, "USD", 1000),
+                Leg::credit(EQUITY, "EUR", 1000),
+            ],
+        );
+
+        assert_eq!(
+            ledger.post(posting),
+            Err(LedgerError::Unbalanced {
+                currency: "EUR".to_string(),
+                difference: -1000
+            })
+        );
+        assert!(ledger.postings().is_empty());
+        assert_eq!(ledger.balance(CASH, "USD"), 0);
+    }
+
+    #[test]
+    fn unknown_account() {
+        let mut ledger = Ledger::default();
+        let posting = Posting::new(
+            "unknown",
+            vec![Leg::debit("9999", "USD", 1), Leg::credit(CASH, "USD", 1)],
+        );
+
+        assert_eq!(
+            ledger.post(posting),
+            Err(LedgerError::UnknownAccount("9999".to_string()))
+        );
+    }
+
+    #[test]
+    fn minor_units_of_the_currency() {
+        assert_eq!(to_minor(12.345, "USD"), 1235);
+        assert_eq!(to_minor(1500., "JPY"), 1500);
+        assert_eq!(to_minor(1.5, "XYZ"), 150);
+    }
+
+    #[test]
+    fn chart_of_accounts() {
+        let mut chart = ChartOfAccounts::standard();
+        assert!(chart.open("5000", "Salaries", Kind::Expense));
+        assert!(!chart.open(CASH, "Cash again", Kind::Asset));
+        assert_eq!(chart.get(CASH).unwrap().kind, Kind::Asset);
+        assert_eq!(chart.iter().count(), 5);
+    }
+}
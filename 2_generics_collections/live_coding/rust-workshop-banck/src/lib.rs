@@ -1,3 +1,14 @@
 pub mod account;
 pub mod bank;
+pub mod budget;
+pub mod calendar;
+pub mod clock;
+pub mod csv;
+pub mod fx;
+pub mod history;
+pub mod idempotency;
+pub mod ledger;
+pub mod loan;
 pub mod money;
+pub mod rate_graph;
+pub mod store;
@@ -0,0 +1,608 @@
+use std::{fmt, time::SystemTime};
+use uuid::Uuid;
+
+use super::account::Account;
+use super::calendar::Month;
+use super::money::TypedMoney;
+
+/// Most installments a loan can be repaid in, a century of monthly installments
+pub const MAX_PERIODS: u32 = 1200;
+
+/// How often the installments of a loan are due
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Frequency {
+    Monthly,
+    Quarterly,
+    SemiAnnual,
+    Annual,
+}
+
+impl Frequency {
+    /// Get the number of months between two installments
+    pub fn months(&self) -> u32 {
+        match self {
+            Frequency::Monthly => 1,
+            Frequency::Quarterly => 3,
+            Frequency::SemiAnnual => 6,
+            Frequency::Annual => 12,
+        }
+    }
+
+    /// Get the number of installments due in a year
+    pub fn per_year(&self) -> u32 {
+        12 / self.months()
+    }
+}
+
+/// How the principal of a loan is repaid over its installments
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Amortization {
+    /// Every installment is the same amount, interest included (French amortization)
+    Annuity,
+    /// Every installment repays the same part of the principal, plus the interest
+    Linear,
+    /// Installments only pay the interest, and the principal is repaid with the last one
+    Bullet,
+}
+
+/// How the installments left are changed by an early repayment
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Adjustment {
+    /// The loan is repaid by the same date, with smaller installments
+    ReducePayment,
+    /// The installments stay the same, and the loan is repaid sooner. A bullet loan
+    /// has no principal to repay before its last installment, so its payments are
+    /// reduced instead.
+    ReduceTerm,
+}
+
+/// The conditions a loan was granted under
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoanTerms {
+    /// The amount lent, in dollar
+    pub principal: f64,
+    /// Nominal yearly interest rate, `0.05` for 5%
+    pub annual_rate: f64,
+    /// Number of installments
+    pub periods: u32,
+    pub frequency: Frequency,
+    pub amortization: Amortization,
+}
+
+/// A payment of the schedule of a loan
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Installment {
+    /// Position of the installment in the schedule, starting at 1
+    pub number: u32,
+    /// The month the installment is due in
+    pub due: Month,
+    /// Part of the payment which pays the interest, in dollar
+    pub interest: f64,
+    /// Part of the payment which repays the principal, in dollar
+    pub principal: f64,
+    /// Principal left to repay once this installment is paid, in dollar
+    pub balance: f64,
+}
+
+impl Installment {
+    /// Get the amount to pay, in dollar
+    pub fn payment(&self) -> f64 {
+        cents(self.interest + self.principal)
+    }
+}
+
+/// A payment made on a loan, split between interest and principal
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Posting {
+    pub at: SystemTime,
+    /// The installment paid, or `None` for an early repayment
+    pub installment: Option<u32>,
+    pub interest: f64,
+    pub principal: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoanError {
+    /// The terms of the loan cannot be repaid
+    InvalidTerms(String),
+    /// The amount of an early repayment is not positive
+    InvalidAmount(f64),
+    /// There is nothing left to pay
+    Repaid,
+}
+
+impl fmt::Display for LoanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoanError::InvalidTerms(reason) => write!(f, "invalid loan terms: {}", reason),
+            LoanError::InvalidAmount(amount) => write!(f, "invalid repayment: {}", amount),
+            LoanError::Repaid => write!(f, "the loan is already repaid"),
+        }
+    }
+}
+
+impl LoanTerms {
+    pub fn new(
+        principal: f64,
+        annual_rate: f64,
+        periods: u32,
+        frequency: Frequency,
+        amortization: Amortization,
+    ) -> Self {
+        Self {
+            principal,
+            annual_rate,
+            periods,
+            frequency,
+            amortization,
+        }
+    }
+
+    /// Get the interest rate of one period between two installments
+    pub fn periodic_rate(&self) -> f64 {
+        self.annual_rate / self.frequency.per_year() as f64
+    }
+
+    /// Check that the loan can be repaid: a positive principal, a rate which is
+    /// not negative, and from one to `MAX_PERIODS` installments.
+    pub fn check(&self) -> Result<(), LoanError> {
+        let error = |reason: &str| Err(LoanError::InvalidTerms(reason.to_string()));
+        if !(self.principal.is_finite() && cents(self.principal) > 0.) {
+            return error("the principal must be positive");
+        }
+        if !(self.annual_rate.is_finite() && self.annual_rate >= 0.) {
+            return error("the rate must not be negative");
+        }
+        if self.periods == 0 {
+            return error("there must be at least one installment");
+        }
+        if self.periods > MAX_PERIODS {
+            return error("there are too many installments");
+        }
+        Ok(())
+    }
+
+    /// Generate the amortization schedule of the loan. Amounts are rounded to the
+    /// cent, the last installment repaying what is left of the principal.
+    ///
+    /// # Arguments
+    /// * `first_due` - the month the first installment is due in
+    pub fn schedule(&self, first_due: Month) -> Vec<Installment> {
+        let splits = amortize(
+            cents(self.principal),
+            self.periodic_rate(),
+            self.periods,
+            self.amortization,
+        );
+        installments(splits, 1, first_due, self.frequency, cents(self.principal))
+    }
+}
+
+/// A loan granted to a customer.
+/// The account of the loan holds what the customer owes as a negative amount, in
+/// the currency of the loan: the principal is retrieved from it when the loan is
+/// granted, and every payment is added back to it once its interest is charged.
+pub struct Loan<'a> {
+    account: Account<'a>,
+    terms: LoanTerms,
+    /// Installments left to pay, the next one first
+    schedule: Vec<Installment>,
+    /// Every payment made, oldest first
+    postings: Vec<Posting>,
+}
+
+impl<'a> Loan<'a> {
+    /// Grant a new loan in currency `T`
+    ///
+    /// # Arguments
+    /// * `name` - the name of the borrower
+    /// * `terms` - the conditions of the loan
+    /// * `now` - the time the loan is granted at. The first installment is due
+    ///   one period after the month of `now`.
+    pub fn new<T: TypedMoney + 'a>(
+        name: &str,
+        terms: LoanTerms,
+        now: SystemTime,
+    ) -> Result<Self, LoanError> {
+        Self::with_id::<T>(name, &Uuid::new_v4(), terms, now)
+    }
+
+    /// Same as `new`, with a given id for the account of the loan.
+    pub fn with_id<T: TypedMoney + 'a>(
+        name: &str,
+        id: &Uuid,
+        terms: LoanTerms,
+        now: SystemTime,
+    ) -> Result<Self, LoanError> {
+        terms.check()?;
+
+        let mut account = Account::with_id::<T>(name, id);
+        account.retrieve_money_at(cents(terms.principal), now);
+        let first_due = Month::containing(now).after(terms.frequency.months());
+        Ok(Self {
+            account,
+            schedule: terms.schedule(first_due),
+            terms,
+            postings: Vec::new(),
+        })
+    }
+
+    /// Get the account of the loan, whose amount is negative while money is owed
+    pub fn get_account(&self) -> &Account<'a> {
+        &self.account
+    }
+    /// Get the conditions the loan was granted under
+    pub fn get_terms(&self) -> &LoanTerms {
+        &self.terms
+    }
+    /// Get the installments left to pay, the next one first
+    pub fn get_schedule(&self) -> &[Installment] {
+        &self.schedule
+    }
+    /// Get every payment made on the loan, oldest first
+    pub fn get_postings(&self) -> &[Posting] {
+        &self.postings
+    }
+
+    /// Get the principal left to repay, in dollar
+    pub fn outstanding(&self) -> f64 {
+        cents(-self.account.get_value()).max(0.)
+    }
+
+    pub fn is_repaid(&self) -> bool {
+        self.schedule.is_empty()
+    }
+
+    /// Pay the next installment of the schedule.
+    ///
+    /// # Arguments
+    /// * `now` - the time of the payment
+    ///
+    /// # Returns
+    /// The payment, split between interest and principal, or an error if the loan is
+    /// already repaid.
+    pub fn pay(&mut self, now: SystemTime) -> Result<Posting, LoanError> {
+        if self.schedule.is_empty() {
+            return Err(LoanError::Repaid);
+        }
+        let installment = self.schedule.remove(0);
+        // The last installment takes care of what rounding left behind
+        let principal = if self.schedule.is_empty() {
+            self.outstanding()
+        } else {
+            installment.principal
+        };
+
+        Ok(self.post(
+            Some(installment.number),
+            installment.interest,
+            principal,
+            now,
+        ))
+    }
+
+    /// Repay part of the principal before it is due. No interest is charged on the
+    /// amount repaid, and the installments left are recomputed.
+    ///
+    /// # Arguments
+    /// * `amount` - the amount to repay, in dollar. Any amount above the outstanding
+    ///   principal is not taken.
+    /// * `adjustment` - how the installments left change
+    /// * `now` - the time of the repayment
+    ///
+    /// # Returns
+    /// The payment made, or an error if the amount is not positive or if the loan is
+    /// already repaid.
+    pub fn repay_early(
+        &mut self,
+        amount: f64,
+        adjustment: Adjustment,
+        now: SystemTime,
+    ) -> Result<Posting, LoanError> {
+        if !(amount.is_finite() && cents(amount) > 0.) {
+            return Err(LoanError::InvalidAmount(amount));
+        }
+        let next = match self.schedule.first() {
+            Some(next) => *next,
+            None => return Err(LoanError::Repaid),
+        };
+
+        let principal = cents(amount).min(self.outstanding());
+        let posting = self.post(None, 0., principal, now);
+
+        let balance = self.outstanding();
+        let rate = self.terms.periodic_rate();
+        let splits = if balance == 0. {
+            Vec::new()
+        } else {
+            match (adjustment, self.terms.amortization) {
+                (Adjustment::ReduceTerm, Amortization::Annuity) => {
+                    level(balance, rate, |interest| next.payment() - interest)
+                }
+                (Adjustment::ReduceTerm, Amortization::Linear) => {
+                    level(balance, rate, |_| next.principal)
+                }
+                (_, amortization) => {
+                    amortize(balance, rate, self.schedule.len() as u32, amortization)
+                }
+            }
+        };
+        self.schedule = installments(splits, next.number, next.due, self.terms.frequency, balance);
+
+        Ok(posting)
+    }
+
+    /// Charge the interest to the account of the loan, then add the payment to it
+    fn post(
+        &mut self,
+        installment: Option<u32>,
+        interest: f64,
+        principal: f64,
+        now: SystemTime,
+    ) -> Posting {
+        if interest > 0. {
+            self.account.retrieve_money_at(interest, now);
+        }
+        self.account.add_money_at(cents(interest + principal), now);
+
+        let posting = Posting {
+            at: now,
+            installment,
+            interest,
+            principal,
+        };
+        self.postings.push(posting);
+        posting
+    }
+}
+
+/// Round an amount of dollars to the cent
+fn cents(amount: f64) -> f64 {
+    (amount * 100.).round() / 100.
+}
+
+/// Split each installment of a loan between interest and principal
+///
+/// # Returns
+/// The interest and the principal of every installment, the last one repaying what
+/// is left of `balance`.
+fn amortize(
+    mut balance: f64,
+    rate: f64,
+    periods: u32,
+    amortization: Amortization,
+) -> Vec<(f64, f64)> {
+    let payment = if rate == 0. {
+        balance / periods as f64
+    } else {
+        balance * rate / (1. - (1. + rate).powi(-(periods as i32)))
+    };
+    let payment = cents(payment);
+    let share = cents(balance / periods as f64);
+
+    let mut splits = Vec::new();
+    for number in 1..=periods {
+        let interest = cents(balance * rate);
+        let principal = if number == periods {
+            balance
+        } else {
+            match amortization {
+                Amortization::Annuity => payment - interest,
+                Amortization::Linear => share,
+                Amortization::Bullet => 0.,
+            }
+            .min(balance)
+        };
+        balance = cents(balance - principal);
+        splits.push((interest, principal));
+    }
+    splits
+}
+
+/// Split installments between interest and principal until `balance` is repaid,
+/// `principal` giving the part of the principal repaid for a given interest.
+fn level<F: Fn(f64) -> f64>(mut balance: f64, rate: f64, principal: F) -> Vec<(f64, f64)> {
+    let mut splits = Vec::new();
+    while balance > 0. {
+        let interest = cents(balance * rate);
+        let mut part = cents(principal(interest)).min(balance);
+        if part <= 0. {
+            // The installments do not repay anything, the rest is due at once
+            part = balance;
+        }
+        balance = cents(balance - part);
+        splits.push((interest, part));
+    }
+    splits
+}
+
+/// Number and date the installments of a schedule
+fn installments(
+    splits: Vec<(f64, f64)>,
+    first_number: u32,
+    first_due: Month,
+    frequency: Frequency,
+    mut balance: f64,
+) -> Vec<Installment> {
+    (0..)
+        .zip(splits)
+        .map(|(i, (interest, principal))| {
+            balance = cents(balance - principal);
+            Installment {
+                number: first_number + i,
+                due: first_due.after(i * frequency.months()),
+                interest,
+                principal,
+                balance,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::calendar::date;
+    use super::super::money::*;
+    use super::*;
+
+    fn terms(amortization: Amortization) -> LoanTerms {
+        LoanTerms::new(1000., 0.12, 12, Frequency::Monthly, amortization)
+    }
+
+    fn total<T>(items: &[T], part: fn(&T) -> f64) -> f64 {
+        cents(items.iter().map(part).sum())
+    }
+
+    #[test]
+    fn annuity_schedule() {
+        let march = Month::new(2021, 3).unwrap();
+        let schedule = terms(Amortization::Annuity).schedule(march);
+
+        assert_eq!(schedule.len(), 12);
+        assert_eq!(schedule[0].interest, 10.);
+        assert_eq!(schedule[0].principal, 78.85);
+        assert!(schedule[..11].iter().all(|i| i.payment() == 88.85));
+        assert_eq!(schedule[11].balance, 0.);
+        assert_eq!(total(&schedule, |i| i.principal), 1000.);
+        assert_eq!(schedule[0].due, march);
+        assert_eq!(schedule[11].due, Month::new(2022, 2).unwrap());
+    }
+
+    #[test]
+    fn linear_schedule() {
+        let mut terms = terms(Amortization::Linear);
+        terms.frequency = Frequency::Quarterly;
+        terms.periods = 3;
+        let schedule = terms.schedule(Month::new(2021, 1).unwrap());
+
+        let splits: Vec<_> = schedule.iter().map(|i| (i.interest, i.principal)).collect();
+        assert_eq!(splits, vec![(30., 333.33), (20., 333.33), (10., 333.34)]);
+        assert_eq!(schedule[2].due, Month::new(2021, 7).unwrap());
+    }
+
+    #[test]
+    fn bullet_schedule() {
+        let schedule = terms(Amortization::Bullet).schedule(Month::new(2021, 1).unwrap());
+
+        assert!(schedule[..11].iter().all(|i| i.payment() == 10.));
+        assert_eq!(schedule[11].payment(), 1010.);
+        assert_eq!(schedule[10].balance, 1000.);
+    }
+
+    #[test]
+    fn without_interest() {
+        let mut terms = terms(Amortization::Annuity);
+        terms.annual_rate = 0.;
+        let schedule = terms.schedule(Month::new(2021, 1).unwrap());
+
+        assert_eq!(schedule[0].payment(), 83.33);
+        assert_eq!(schedule[11].payment(), 83.37);
+        assert_eq!(total(&schedule, |i| i.interest), 0.);
+    }
+
+    #[test]
+    fn invalid_terms() {
+        let now = SystemTime::UNIX_EPOCH;
+        let mut invalid = terms(Amortization::Annuity);
+        invalid.periods = 0;
+        assert!(Loan::new::<Dollar>("loan", invalid, now).is_err());
+        invalid.periods = MAX_PERIODS + 1;
+        assert!(Loan::new::<Dollar>("loan", invalid, now).is_err());
+        invalid.periods = u32::MAX;
+        assert!(Loan::new::<Dollar>("loan", invalid, now).is_err());
+        invalid.periods = MAX_PERIODS;
+        assert!(Loan::new::<Dollar>("loan", invalid, now).is_ok());
+        invalid = terms(Amortization::Annuity);
+        invalid.annual_rate = -0.1;
+        assert!(Loan::new::<Dollar>("loan", invalid, now).is_err());
+        invalid = terms(Amortization::Annuity);
+        invalid.principal = f64::NAN;
+        assert!(matches!(
+            Loan::new::<Dollar>("loan", invalid, now),
+            Err(LoanError::InvalidTerms(_))
+        ));
+    }
+
+    #[test]
+    fn payments() {
+        let now = date(2021, 2, 14).unwrap();
+        let mut loan = Loan::new::<Euro>("loan", terms(Amortization::Annuity), now).unwrap();
+        assert_eq!(loan.outstanding(), 1000.);
+        assert_eq!(loan.get_account().get_currency(), "EUR");
+        assert_eq!(loan.get_schedule()[0].due, Month::new(2021, 3).unwrap());
+
+        let posting = loan.pay(now).unwrap();
+        assert_eq!(posting.installment, Some(1));
+        assert_eq!((posting.interest, posting.principal), (10., 78.85));
+        assert_eq!(loan.outstanding(), 921.15);
+        assert_eq!(loan.get_schedule().len(), 11);
+
+        for _ in 0..11 {
+            loan.pay(now).unwrap();
+        }
+        assert!(loan.is_repaid());
+        assert_eq!(loan.outstanding(), 0.);
+        assert_eq!(total(loan.get_postings(), |p| p.principal), 1000.);
+        assert_eq!(loan.pay(now), Err(LoanError::Repaid));
+    }
+
+    #[test]
+    fn early_repayment_reduces_payment() {
+        let now = SystemTime::UNIX_EPOCH;
+        let mut loan = Loan::new::<Dollar>("loan", terms(Amortization::Linear), now).unwrap();
+        loan.pay(now).unwrap();
+
+        let posting = loan
+            .repay_early(183.33, Adjustment::ReducePayment, now)
+            .unwrap();
+        assert_eq!((posting.installment, posting.interest), (None, 0.));
+        assert_eq!(loan.outstanding(), 733.34);
+
+        let schedule = loan.get_schedule();
+        assert_eq!(schedule.len(), 11);
+        assert_eq!(schedule[0].number, 2);
+        assert_eq!(schedule[0].due, Month::new(1970, 3).unwrap());
+        assert_eq!(schedule[0].principal, 66.67);
+        assert_eq!(total(schedule, |i| i.principal), 733.34);
+    }
+
+    #[test]
+    fn early_repayment_reduces_term() {
+        let now = SystemTime::UNIX_EPOCH;
+        let mut loan = Loan::new::<Dollar>("loan", terms(Amortization::Annuity), now).unwrap();
+        loan.pay(now).unwrap();
+
+        loan.repay_early(300., Adjustment::ReduceTerm, now).unwrap();
+        let schedule = loan.get_schedule();
+        assert_eq!(schedule.len(), 8);
+        assert!(schedule[..7].iter().all(|i| i.payment() == 88.85));
+        assert!(schedule[7].payment() < 88.85);
+        assert_eq!(total(schedule, |i| i.principal), 621.15);
+
+        for _ in 0..8 {
+            loan.pay(now).unwrap();
+        }
+        assert!(loan.is_repaid());
+        assert!(loan.get_account().get_amount().abs() < 1e-9);
+    }
+
+    #[test]
+    fn early_repayment_of_everything() {
+        let now = SystemTime::UNIX_EPOCH;
+        let mut loan = Loan::new::<Dollar>("loan", terms(Amortization::Bullet), now).unwrap();
+
+        assert_eq!(
+            loan.repay_early(0., Adjustment::ReduceTerm, now),
+            Err(LoanError::InvalidAmount(0.))
+        );
+        let posting = loan
+            .repay_early(5000., Adjustment::ReduceTerm, now)
+            .unwrap();
+        assert_eq!(posting.principal, 1000.);
+        assert!(loan.is_repaid());
+        assert_eq!(
+            loan.repay_early(1., Adjustment::ReduceTerm, now),
+            Err(LoanError::Repaid)
+        );
+    }
+}
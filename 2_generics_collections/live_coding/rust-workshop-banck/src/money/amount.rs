@@ -0,0 +1,85 @@
+use super::{Currency, Money};
+
+/// An amount of money in a currency only known at runtime
+#[derive(Debug, Clone, PartialEq)]
+pub struct Amount {
+    currency: Currency,
+    amount: f64,
+}
+
+impl Amount {
+    /// Create an amount of money
+    ///
+    /// # Arguments
+    /// * `currency` - the currency of this amount
+    /// * `amount` - the amount of money, in `currency`
+    pub fn new(currency: Currency, amount: f64) -> Self {
+        Self { currency, amount }
+    }
+
+    /// Create an amount of money from a dollar value
+    ///
+    /// # Arguments
+    /// * `currency` - the currency of this amount
+    /// * `dollars` - the dollar value, converted into `currency`
+    pub fn from_dollars(currency: Currency, dollars: f64) -> Self {
+        let amount = dollars / currency.rate;
+        Self::new(currency, amount)
+    }
+
+    /// Get the description of the currency of this amount
+    pub fn unit(&self) -> &Currency {
+        &self.currency
+    }
+}
+
+impl Money for Amount {
+    fn amount(&self) -> f64 {
+        self.amount
+    }
+
+    fn value(&self) -> f64 {
+        self.amount * self.currency.rate
+    }
+
+    fn add(&mut self, other: f64) {
+        self.amount += other / self.currency.rate;
+    }
+
+    fn remove(&mut self, other: f64) {
+        self.amount -= other / self.currency.rate;
+    }
+
+    fn add_amount(&mut self, amount: f64) {
+        self.amount += amount;
+    }
+
+    fn remove_amount(&mut self, amount: f64) {
+        self.amount -= amount;
+    }
+
+    fn currency(&self) -> &str {
+        &self.currency.code
+    }
+
+    fn rate(&self) -> f64 {
+        self.currency.rate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::standard;
+    use super::*;
+
+    #[test]
+    fn from_dollars() {
+        let yen = standard().get("JPY").unwrap().clone();
+        let mut amount = Amount::from_dollars(yen, 91.);
+
+        assert!((amount.amount() - 10000.).abs() < 1e-6);
+        assert_eq!(amount.currency(), "JPY");
+        amount.remove(91.);
+        assert!(amount.amount().abs() < 1e-6);
+    }
+}
@@ -0,0 +1,255 @@
+use std::{collections::BTreeMap, io::BufRead, sync::OnceLock};
+
+use super::super::csv::CsvError;
+use super::{Amount, Code, Eur, Mru, Usd};
+
+/// The currencies known to this banck by default, in the CSV format read by
+/// `Registry::load_csv`
+const ISO_4217: &str = include_str!("iso_4217.csv");
+
+static STANDARD: OnceLock<Registry> = OnceLock::new();
+
+/// Most digits a currency may have after its decimal separator, so that a power
+/// of ten of them fits the arithmetic of amounts
+pub const MAX_MINOR_UNITS: u8 = 18;
+
+/// Description of a currency, as defined by ISO 4217
+#[derive(Debug, Clone, PartialEq)]
+pub struct Currency {
+    /// Alphabetic code, such as `"EUR"`
+    pub code: String,
+    /// Numeric code, such as `978`
+    pub numeric: u16,
+    /// Number of digits after the decimal separator, 2 for cents
+    pub minor_units: u8,
+    pub symbol: String,
+    pub name: String,
+    /// Exchange rate from this currency into american dollar
+    pub rate: f64,
+}
+
+impl Currency {
+    pub fn new(
+        code: &str,
+        numeric: u16,
+        minor_units: u8,
+        symbol: &str,
+        name: &str,
+        rate: f64,
+    ) -> Self {
+        Self {
+            code: code.to_string(),
+            numeric,
+            minor_units,
+            symbol: symbol.to_string(),
+            name: name.to_string(),
+            rate,
+        }
+    }
+}
+
+/// A set of currencies, indexed by their alphabetic code
+#[derive(Debug, Clone, Default)]
+pub struct Registry {
+    currencies: BTreeMap<String, Currency>,
+}
+
+impl Registry {
+    /// Create a registry without any currency
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry holding the currencies known to this banck by default,
+    /// read from the `iso_4217.csv` table shipped with it
+    pub fn iso_4217() -> Self {
+        let mut registry = Self::new();
+        registry
+            .load_csv(ISO_4217.as_bytes())
+            .expect("the default currency table is valid");
+        registry
+    }
+
+    /// Register currencies read from CSV, one per line:
+    /// `code,numeric,minor_units,symbol,name,rate`, the rate being the dollar value
+    /// of one unit and the minor units at most `MAX_MINOR_UNITS`. A first line
+    /// starting with `code` is taken as a header, and empty lines are skipped.
+    ///
+    /// # Returns
+    /// The number of currencies registered. Nothing is registered if a line is
+    /// invalid or uses a code already registered.
+    pub fn load_csv<R: BufRead>(&mut self, reader: R) -> Result<usize, CsvError> {
+        let mut currencies: Vec<Currency> = Vec::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || (i == 0 && line.starts_with("code")) {
+                continue;
+            }
+            let error = |reason: &str| CsvError::Line {
+                line: i + 1,
+                reason: reason.to_string(),
+            };
+
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            if fields.len() != 6 {
+                return Err(error(
+                    "expected code, numeric code, minor units, symbol, name and rate",
+                ));
+            }
+            if fields[0].is_empty() {
+                return Err(error("missing code"));
+            }
+            if self.get(fields[0]).is_some() || currencies.iter().any(|c| c.code == fields[0]) {
+                return Err(error("code already registered"));
+            }
+            let numeric = fields[1]
+                .parse()
+                .map_err(|_| error("invalid numeric code"))?;
+            let minor_units = fields[2]
+                .parse::<u8>()
+                .ok()
+                .filter(|minor_units| *minor_units <= MAX_MINOR_UNITS)
+                .ok_or_else(|| error("invalid minor units"))?;
+            let rate = fields[5]
+                .parse::<f64>()
+                .ok()
+                .filter(|rate| rate.is_finite() && *rate > 0.)
+                .ok_or_else(|| error("invalid rate"))?;
+            currencies.push(Currency::new(
+                fields[0],
+                numeric,
+                minor_units,
+                fields[3],
+                fields[4],
+                rate,
+            ));
+        }
+
+        let count = currencies.len();
+        for currency in currencies {
+            self.register(currency);
+        }
+        Ok(count)
+    }
+
+    /// Return true if the currency was registered successfully, false if its code
+    /// was already used
+    pub fn register(&mut self, currency: Currency) -> bool {
+        if self.currencies.contains_key(&currency.code) {
+            return false;
+        }
+        self.currencies.insert(currency.code.clone(), currency);
+        true
+    }
+
+    /// Get a currency from its alphabetic code
+    pub fn get(&self, code: &str) -> Option<&Currency> {
+        self.currencies.get(code)
+    }
+
+    /// Get a currency from its numeric code
+    pub fn get_numeric(&self, numeric: u16) -> Option<&Currency> {
+        self.currencies
+            .values()
+            .find(|currency| currency.numeric == numeric)
+    }
+
+    /// Build an amount of money in the currency `code`
+    ///
+    /// # Returns
+    /// The amount, or `None` if the currency is not registered.
+    pub fn amount(&self, code: &str, amount: f64) -> Option<Amount> {
+        self.get(code)
+            .map(|currency| Amount::new(currency.clone(), amount))
+    }
+
+    /// Iterate over every currency, ordered by code
+    pub fn iter(&self) -> impl Iterator<Item = &Currency> {
+        self.currencies.values()
+    }
+}
+
+/// Get the registry of the currencies known to this banck. Unless another one was
+/// installed first, this is `Registry::iso_4217`. The registry can no longer change
+/// once it was used.
+pub fn standard() -> &'static Registry {
+    STANDARD.get_or_init(Registry::iso_4217)
+}
+
+/// Use `registry` as the registry of the currencies known to this banck, for
+/// example to add currencies read from a file at startup. This must be done before
+/// any money is created.
+///
+/// # Returns
+/// The registry back, if the registry of the banck was already used, or if it lacks
+/// one of the currencies of the `Dollar`, `Euro` and `Ouguiya` types.
+pub fn install(registry: Registry) -> Result<(), Registry> {
+    if [Usd::CODE, Eur::CODE, Mru::CODE]
+        .iter()
+        .any(|code| registry.get(code).is_none())
+    {
+        return Err(registry);
+    }
+    STANDARD.set(registry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Money;
+    use super::*;
+
+    #[test]
+    fn lookup() {
+        let registry = Registry::iso_4217();
+
+        let euro = registry.get("EUR").unwrap();
+        assert_eq!(euro.numeric, 978);
+        assert_eq!(euro.minor_units, 2);
+        assert_eq!(euro.symbol, "€");
+        assert_eq!(registry.get_numeric(392).unwrap().code, "JPY");
+        assert!(registry.get("XXX").is_none());
+    }
+
+    #[test]
+    fn load_csv() {
+        let mut registry = Registry::new();
+        let table = "code,numeric,minor_units,symbol,name,rate\n\nCHF,756,2,Fr,Swiss Franc,1.09\n";
+        assert_eq!(registry.load_csv(table.as_bytes()).unwrap(), 1);
+        assert_eq!(registry.get("CHF").unwrap().name, "Swiss Franc");
+
+        let line = |table: &str| match registry.clone().load_csv(table.as_bytes()) {
+            Err(CsvError::Line { line, .. }) => line,
+            other => panic!("unexpected result: {:?}", other),
+        };
+        assert_eq!(
+            line("SEK,752,2,kr,Krona,0.1\nCHF,756,2,Fr,Swiss Franc,1.09"),
+            2
+        );
+        assert_eq!(line("SEK,752,2,kr,Krona"), 1);
+        assert_eq!(line("SEK,-752,2,kr,Krona,0.1"), 1);
+        assert_eq!(line("SEK,752,2,kr,Krona,0"), 1);
+        assert_eq!(line("SEK,752,255,kr,Krona,0.1"), 1);
+        assert_eq!(line("SEK,752,19,kr,Krona,0.1"), 1);
+        assert_eq!(line("SEK,752,2,kr,Krona,0.1\nSEK,752,2,kr,Krona,0.1"), 2);
+        assert!(registry.get("SEK").is_none());
+    }
+
+    #[test]
+    fn install_after_use() {
+        assert_eq!(standard().get("USD").unwrap().numeric, 840);
+        assert!(install(Registry::iso_4217()).is_err());
+        assert!(install(Registry::new()).is_err());
+    }
+
+    #[test]
+    fn register() {
+        let mut registry = Registry::new();
+
+        assert!(registry.register(Currency::new("CHF", 756, 2, "CHF", "Swiss Franc", 1.09)));
+        assert!(!registry.register(Currency::new("CHF", 756, 2, "Fr", "Franc", 1.)));
+        assert_eq!(registry.get("CHF").unwrap().symbol, "CHF");
+        assert_eq!(registry.amount("CHF", 2.).unwrap().value(), 2.18);
+        assert!(registry.amount("EUR", 2.).is_none());
+    }
+}
@@ -0,0 +1,491 @@
+use std::fmt;
+
+use super::{standard, Amount, Code, Currency, Money, Registry, Typed, TypedMoney};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymbolPosition {
+    /// `$1.00`
+    Before,
+    /// `1,00 €`
+    After,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NegativeStyle {
+    /// `-$1.00`
+    Minus,
+    /// `($1.00)`, as used in accounting
+    Parentheses,
+}
+
+/// Conventions used to write amounts of money
+#[derive(Debug, Clone, PartialEq)]
+pub struct Locale {
+    pub decimal_separator: char,
+    /// Separator between groups of three digits, if digits are grouped
+    pub grouping_separator: Option<char>,
+    pub symbol_position: SymbolPosition,
+    /// Whether a space is put between the symbol and the number
+    pub symbol_spaced: bool,
+    pub negative: NegativeStyle,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    /// Nothing to parse
+    Empty,
+    /// The string holds a number, but no currency symbol or code
+    MissingCurrency,
+    /// The symbol or code of the string is not registered
+    UnknownCurrency(String),
+    /// The string holds an amount in another currency than the one requested
+    WrongCurrency { expected: String, found: String },
+    /// A character which is not part of a number in this locale
+    InvalidCharacter(char),
+    /// Digits are not grouped by three
+    MisplacedGrouping,
+    /// More decimals than the minor units of the currency
+    TooManyDecimals { allowed: u8, found: usize },
+    /// The number is malformed, for example with two decimal separators
+    InvalidNumber(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "empty amount"),
+            ParseError::MissingCurrency => write!(f, "missing currency symbol or code"),
+            ParseError::UnknownCurrency(currency) => write!(f, "unknown currency: {}", currency),
+            ParseError::WrongCurrency { expected, found } => {
+                write!(f, "expected an amount in {}, found {}", expected, found)
+            }
+            ParseError::InvalidCharacter(c) => write!(f, "invalid character: {:?}", c),
+            ParseError::MisplacedGrouping => write!(f, "digits must be grouped by three"),
+            ParseError::TooManyDecimals { allowed, found } => {
+                write!(f, "{} decimals found, at most {} allowed", found, allowed)
+            }
+            ParseError::InvalidNumber(number) => write!(f, "invalid number: {}", number),
+        }
+    }
+}
+
+impl Locale {
+    /// `$1,234.56`, `-$1,234.56`
+    pub fn en_us() -> Self {
+        Self {
+            decimal_separator: '.',
+            grouping_separator: Some(','),
+            symbol_position: SymbolPosition::Before,
+            symbol_spaced: false,
+            negative: NegativeStyle::Minus,
+        }
+    }
+
+    /// `1 234,56 €`, `-1 234,56 €`
+    pub fn fr_fr() -> Self {
+        Self {
+            decimal_separator: ',',
+            grouping_separator: Some(' '),
+            symbol_position: SymbolPosition::After,
+            symbol_spaced: true,
+            negative: NegativeStyle::Minus,
+        }
+    }
+
+    /// `1.234,56 €`, `-1.234,56 €`
+    pub fn de_de() -> Self {
+        Self {
+            grouping_separator: Some('.'),
+            ..Self::fr_fr()
+        }
+    }
+
+    /// Write an amount of money
+    ///
+    /// # Arguments
+    /// * `amount` - the amount, in `currency`. It is rounded to the minor units of `currency`.
+    /// * `currency` - the currency of the amount
+    pub fn format(&self, amount: f64, currency: &Currency) -> String {
+        let scale = 10f64.powi(currency.minor_units as i32);
+        self.format_minor((amount * scale).round() as i128, currency)
+    }
+
+    /// Write an amount of money counted in minor units, such as cents, exactly
+    ///
+    /// # Arguments
+    /// * `minor` - the amount, in minor units of `currency`
+    /// * `currency` - the currency of the amount
+    pub fn format_minor(&self, minor: i128, currency: &Currency) -> String {
+        let scale = 10u128.pow(currency.minor_units as u32);
+        let negative = minor < 0;
+        let minor = minor.unsigned_abs();
+
+        let mut number = self.group(&(minor / scale).to_string());
+        if currency.minor_units > 0 {
+            number.push(self.decimal_separator);
+            number.push_str(&format!(
+                "{:0width$}",
+                minor % scale,
+                width = currency.minor_units as usize
+            ));
+        }
+
+        let space = if self.symbol_spaced { " " } else { "" };
+        let body = match self.symbol_position {
+            SymbolPosition::Before => format!("{}{}{}", currency.symbol, space, number),
+            SymbolPosition::After => format!("{}{}{}", number, space, currency.symbol),
+        };
+
+        match (negative, self.negative) {
+            (false, _) => body,
+            (true, NegativeStyle::Minus) => format!("-{}", body),
+            (true, NegativeStyle::Parentheses) => format!("({})", body),
+        }
+    }
+
+    /// Write an amount of money held in an `Amount`
+    pub fn format_money(&self, money: &Amount) -> String {
+        self.format(money.amount(), money.unit())
+    }
+
+    /// Read an amount of money, written with the conventions of this locale.
+    /// The currency is identified by its symbol or ISO 4217 code, before or after
+    /// the number.
+    ///
+    /// # Arguments
+    /// * `input` - the string to read, such as `"$1,234.56"`
+    /// * `registry` - the currencies the amount can be in
+    pub fn parse(&self, input: &str, registry: &Registry) -> Result<Amount, ParseError> {
+        let mut text = input.trim();
+        if text.is_empty() {
+            return Err(ParseError::Empty);
+        }
+
+        let mut negative = false;
+        if text.starts_with('(') && text.ends_with(')') {
+            negative = true;
+            text = text[1..text.len() - 1].trim();
+        }
+        if let Some(rest) = text.strip_prefix('-') {
+            negative = true;
+            text = rest.trim_start();
+        }
+
+        let (currency, rest) = find_currency(text, registry)?;
+        let mut number = rest.trim();
+        if let Some(rest) = number.strip_prefix('-') {
+            negative = true;
+            number = rest.trim_start();
+        }
+
+        let amount = self.parse_number(number, currency.minor_units)?;
+        Ok(Amount::new(
+            currency.clone(),
+            if negative { -amount } else { amount },
+        ))
+    }
+
+    /// Read an amount of money in a currency known at compile time
+    pub fn parse_typed<C: Code>(&self, input: &str) -> Result<Typed<C>, ParseError> {
+        let amount = self.parse(input, standard())?;
+        if amount.currency() != C::CODE {
+            return Err(ParseError::WrongCurrency {
+                expected: C::CODE.to_string(),
+                found: amount.currency().to_string(),
+            });
+        }
+        Ok(Typed::from_amount(amount.amount()))
+    }
+
+    /// Insert the grouping separator every three digits
+    fn group(&self, digits: &str) -> String {
+        let separator = match self.grouping_separator {
+            Some(separator) => separator,
+            None => return digits.to_string(),
+        };
+
+        let mut grouped = String::new();
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i) % 3 == 0 {
+                grouped.push(separator);
+            }
+            grouped.push(digit);
+        }
+        grouped
+    }
+
+    fn is_grouping(&self, c: char) -> bool {
+        match self.grouping_separator {
+            // Spaces are often written as non-breaking spaces
+            Some(separator) if separator.is_whitespace() => c.is_whitespace(),
+            Some(separator) => c == separator,
+            None => false,
+        }
+    }
+
+    fn parse_number(&self, number: &str, minor_units: u8) -> Result<f64, ParseError> {
+        if number.is_empty() {
+            return Err(ParseError::InvalidNumber(number.to_string()));
+        }
+        if let Some(c) = number
+            .chars()
+            .find(|c| !c.is_ascii_digit() && *c != self.decimal_separator && !self.is_grouping(*c))
+        {
+            return Err(ParseError::InvalidCharacter(c));
+        }
+
+        let mut parts = number.split(self.decimal_separator);
+        let integer = parts.next().unwrap_or("");
+        let fraction = parts.next();
+        if parts.next().is_some() || integer.is_empty() || fraction == Some("") {
+            return Err(ParseError::InvalidNumber(number.to_string()));
+        }
+
+        let groups: Vec<&str> = integer.split(|c| self.is_grouping(c)).collect();
+        let well_grouped = groups.len() == 1
+            || ((1..=3).contains(&groups[0].len()) && groups[1..].iter().all(|g| g.len() == 3));
+        if !well_grouped {
+            return Err(ParseError::MisplacedGrouping);
+        }
+
+        if let Some(fraction) = fraction {
+            if let Some(c) = fraction.chars().find(|c| !c.is_ascii_digit()) {
+                return Err(ParseError::InvalidCharacter(c));
+            }
+            if fraction.len() > minor_units as usize {
+                return Err(ParseError::TooManyDecimals {
+                    allowed: minor_units,
+                    found: fraction.len(),
+                });
+            }
+        }
+
+        format!("{}.{}", groups.concat(), fraction.unwrap_or("0"))
+            .parse()
+            .map_err(|_| ParseError::InvalidNumber(number.to_string()))
+    }
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self::en_us()
+    }
+}
+
+/// Find the currency whose symbol or code starts or ends `text`
+///
+/// # Returns
+/// The currency, and the rest of `text`.
+fn find_currency<'r, 't>(
+    text: &'t str,
+    registry: &'r Registry,
+) -> Result<(&'r Currency, &'t str), ParseError> {
+    let mut found: Option<(&Currency, &str, usize)> = None;
+
+    for currency in registry.iter() {
+        for mark in [&currency.symbol, &currency.code].iter() {
+            let rest = text
+                .strip_prefix(mark.as_str())
+                .or_else(|| text.strip_suffix(mark.as_str()));
+            let longest = found.map_or(true, |(_, _, len)| mark.len() > len);
+            if let (Some(rest), true) = (rest, longest) {
+                found = Some((currency, rest, mark.len()));
+            }
+        }
+    }
+
+    match found {
+        Some((currency, rest, _)) => Ok((currency, rest)),
+        None => {
+            let is_number =
+                |c: char| c.is_ascii_digit() || c.is_whitespace() || ".,'-()".contains(c);
+            let mark: String = text
+                .trim_start_matches(is_number)
+                .trim_end_matches(is_number)
+                .to_string();
+            if mark.is_empty() {
+                Err(ParseError::MissingCurrency)
+            } else {
+                Err(ParseError::UnknownCurrency(mark))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Locale::default().format_money(self))
+    }
+}
+
+impl<C: Code> fmt::Display for Typed<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            Locale::default().format(self.amount(), Self::unit())
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Dollar, Eur, Euro, Usd};
+    use super::*;
+
+    fn currency(code: &str) -> &'static Currency {
+        standard().get(code).unwrap()
+    }
+
+    #[test]
+    fn format() {
+        assert_eq!(
+            Locale::en_us().format(1234.56, currency("USD")),
+            "$1,234.56"
+        );
+        assert_eq!(Locale::en_us().format(-0.5, currency("USD")), "-$0.50");
+        assert_eq!(
+            Locale::fr_fr().format(1234.56, currency("EUR")),
+            "1 234,56 €"
+        );
+        assert_eq!(
+            Locale::de_de().format(1234567.8, currency("EUR")),
+            "1.234.567,80 €"
+        );
+        assert_eq!(Locale::en_us().format(1234.5, currency("JPY")), "¥1,235");
+        assert_eq!(Locale::en_us().format(12., currency("USD")), "$12.00");
+        assert_eq!(Locale::en_us().format(-0.001, currency("USD")), "$0.00");
+    }
+
+    #[test]
+    fn format_minor() {
+        assert_eq!(
+            Locale::en_us().format_minor(u64::MAX as i128, currency("USD")),
+            "$184,467,440,737,095,516.15"
+        );
+        assert_eq!(Locale::fr_fr().format_minor(-5, currency("EUR")), "-0,05 €");
+        assert_eq!(Locale::en_us().format_minor(-5, currency("JPY")), "-¥5");
+    }
+
+    #[test]
+    fn format_negative_styles() {
+        let accounting = Locale {
+            negative: NegativeStyle::Parentheses,
+            ..Locale::en_us()
+        };
+        assert_eq!(accounting.format(-3.2, currency("USD")), "($3.20)");
+
+        let ungrouped = Locale {
+            grouping_separator: None,
+            ..Locale::en_us()
+        };
+        assert_eq!(ungrouped.format(1234., currency("USD")), "$1234.00");
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Euro::from_amount(1234.5).to_string(), "€1,234.50");
+        assert_eq!(Dollar::from_amount(-3.).to_string(), "-$3.00");
+        assert_eq!(standard().amount("GBP", 0.07).unwrap().to_string(), "£0.07");
+    }
+
+    #[test]
+    fn parse() {
+        let registry = standard();
+
+        let euros = Locale::fr_fr().parse("1 234,56 €", registry).unwrap();
+        assert_eq!(euros.currency(), "EUR");
+        assert_eq!(euros.amount(), 1234.56);
+
+        let dollars = Locale::en_us().parse("$1,234.56", registry).unwrap();
+        assert_eq!(dollars.currency(), "USD");
+        assert_eq!(dollars.amount(), 1234.56);
+
+        let negative = Locale::en_us().parse("-$3.20", registry).unwrap();
+        assert_eq!(negative.amount(), -3.2);
+        let negative = Locale::en_us().parse("($3.20)", registry).unwrap();
+        assert_eq!(negative.amount(), -3.2);
+        let negative = Locale::fr_fr().parse("-12 EUR", registry).unwrap();
+        assert_eq!(negative.amount(), -12.);
+
+        let spaced = Locale::fr_fr()
+            .parse("1\u{a0}234,5\u{a0}€", registry)
+            .unwrap();
+        assert_eq!(spaced.amount(), 1234.5);
+        assert_eq!(
+            Locale::en_us().parse("¥1,235", registry).unwrap().amount(),
+            1235.
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let registry = standard();
+        let us = Locale::en_us();
+
+        assert_eq!(us.parse("  ", registry), Err(ParseError::Empty));
+        assert_eq!(
+            us.parse("12.00", registry),
+            Err(ParseError::MissingCurrency)
+        );
+        assert_eq!(
+            us.parse("12.00 XYZ", registry),
+            Err(ParseError::UnknownCurrency("XYZ".to_string()))
+        );
+        assert_eq!(
+            us.parse("$12.345", registry),
+            Err(ParseError::TooManyDecimals {
+                allowed: 2,
+                found: 3
+            })
+        );
+        assert_eq!(
+            us.parse("¥12.5", registry),
+            Err(ParseError::TooManyDecimals {
+                allowed: 0,
+                found: 1
+            })
+        );
+        assert_eq!(
+            us.parse("$12,34.00", registry),
+            Err(ParseError::MisplacedGrouping)
+        );
+        assert_eq!(
+            us.parse("$1a2", registry),
+            Err(ParseError::InvalidCharacter('a'))
+        );
+        assert_eq!(
+            us.parse("$1.2.3", registry),
+            Err(ParseError::InvalidNumber("1.2.3".to_string()))
+        );
+        assert_eq!(
+            Locale::fr_fr().parse("1,234.56 €", registry),
+            Err(ParseError::InvalidCharacter('.'))
+        );
+    }
+
+    #[test]
+    fn parse_typed() {
+        let euros: Euro = Locale::fr_fr().parse_typed::<Eur>("3,50 €").unwrap();
+        assert_eq!(euros.amount(), 3.5);
+
+        assert_eq!(
+            Locale::en_us().parse_typed::<Usd>("€3.50"),
+            Err(ParseError::WrongCurrency {
+                expected: "USD".to_string(),
+                found: "EUR".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        for locale in [Locale::en_us(), Locale::fr_fr(), Locale::de_de()].iter() {
+            for amount in [0., 0.01, -7.5, 999.99, 1000., 1234567.89].iter() {
+                let written = locale.format(*amount, currency("EUR"));
+                let read = locale.parse(&written, standard()).unwrap();
+                assert_eq!(read.amount(), *amount, "{}", written);
+            }
+        }
+    }
+}
@@ -1,15 +1,20 @@
-pub mod dollar;
-pub mod euro;
-pub mod ouguiya;
+pub mod amount;
+pub mod currency;
+pub mod format;
+pub mod ops;
+pub mod typed;
 
-pub use dollar::Dollar;
-pub use euro::Euro;
-pub use ouguiya::Ouguiya;
+pub use amount::Amount;
+pub use currency::{install, standard, Currency, Registry};
+pub use format::{Locale, NegativeStyle, ParseError, SymbolPosition};
+pub use typed::{Code, Eur, Mru, Typed, Usd};
+
+pub type Dollar = Typed<Usd>;
+pub type Euro = Typed<Eur>;
+pub type Ouguiya = Typed<Mru>;
 
 /// Represent a currency
 pub trait Money {
-    /// Get the exchange rate from this currency into american dollar
-    fn exchange_rate() -> f64 where Self: Sized;
     /// Retrieve the amount of money storred in this currency
     fn amount(&self) -> f64;
     /// Return the value of money storred in this currency, converted into american dollar
@@ -25,7 +30,32 @@ pub trait Money {
     fn add(&mut self, other: f64);
     /// Remove the amount of money stored in `other` converted into this currency
     fn remove(&mut self, other: f64);
+    /// Add an amount of money already in this currency
+    fn add_amount(&mut self, amount: f64);
+    /// Remove an amount of money already in this currency
+    fn remove_amount(&mut self, amount: f64);
+    /// Get the ISO 4217 code of this currency
+    fn currency(&self) -> &str;
+    /// Get the exchange rate from this currency into american dollar
+    fn rate(&self) -> f64;
+}
 
+/// Represent a currency known at compile time
+pub trait TypedMoney: Money + Sized {
+    /// Get the exchange rate from this currency into american dollar
+    fn exchange_rate() -> f64;
     /// Allow construction of a money object from a dollar value.
-    fn from(other: f64) -> Self where Self: Sized;
+    fn from(other: f64) -> Self;
+    /// Allow construction of a money object from an amount already in this currency.
+    fn from_amount(amount: f64) -> Self;
+}
+
+/// Build a money object from a currency code and an amount in that currency.
+///
+/// # Returns
+/// The money object, or `None` if the currency is not known.
+pub fn from_currency(code: &str, amount: f64) -> Option<Box<dyn Money>> {
+    standard()
+        .amount(code, amount)
+        .map(|amount| Box::new(amount) as Box<dyn Money>)
 }
@@ -0,0 +1,194 @@
+use std::{
+    cmp::Ordering,
+    iter::Sum,
+    ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign},
+};
+
+use super::{Code, Money, Typed, TypedMoney};
+use crate::fx::RateTable;
+
+// Arithmetic is only defined between amounts of the same currency: adding euros to
+// dollars does not compile, and has to go through `convert_to`.
+
+impl<C: Code> Typed<C> {
+    /// Add two amounts of money
+    ///
+    /// # Returns
+    /// The sum, or `None` if it overflows.
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        Self::checked(self.amount() + other.amount())
+    }
+
+    /// Subtract an amount of money from this one
+    ///
+    /// # Returns
+    /// The difference, or `None` if it overflows.
+    pub fn checked_sub(&self, other: &Self) -> Option<Self> {
+        Self::checked(self.amount() - other.amount())
+    }
+
+    /// Multiply this amount of money by a scalar
+    ///
+    /// # Returns
+    /// The product, or `None` if it overflows or `factor` is not a number.
+    pub fn checked_mul(&self, factor: f64) -> Option<Self> {
+        Self::checked(self.amount() * factor)
+    }
+
+    /// Convert this amount of money into another currency
+    ///
+    /// # Arguments
+    /// * `rates` - the exchange rates to convert with
+    ///
+    /// # Returns
+    /// The converted amount, or `None` if `rates` lacks one of the two currencies.
+    pub fn convert_to<T: Code>(&self, rates: &RateTable) -> Option<Typed<T>> {
+        let (rate, _) = rates.rate(C::CODE, T::CODE)?;
+        Some(Typed::from_amount(self.amount() * rate))
+    }
+
+    fn checked(amount: f64) -> Option<Self> {
+        if amount.is_finite() {
+            Some(Self::from_amount(amount))
+        } else {
+            None
+        }
+    }
+}
+
+impl<C: Code> Add for Typed<C> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::from_amount(self.amount() + other.amount())
+    }
+}
+
+impl<C: Code> AddAssign for Typed<C> {
+    fn add_assign(&mut self, other: Self) {
+        *self = Self::from_amount(self.amount() + other.amount());
+    }
+}
+
+impl<C: Code> Sub for Typed<C> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::from_amount(self.amount() - other.amount())
+    }
+}
+
+impl<C: Code> SubAssign for Typed<C> {
+    fn sub_assign(&mut self, other: Self) {
+        *self = Self::from_amount(self.amount() - other.amount());
+    }
+}
+
+impl<C: Code> Neg for Typed<C> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::from_amount(-self.amount())
+    }
+}
+
+impl<C: Code> Mul<f64> for Typed<C> {
+    type Output = Self;
+
+    fn mul(self, factor: f64) -> Self {
+        Self::from_amount(self.amount() * factor)
+    }
+}
+
+impl<C: Code> Sum for Typed<C> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        Self::from_amount(iter.map(|money| money.amount()).sum())
+    }
+}
+
+impl<'a, C: Code> Sum<&'a Typed<C>> for Typed<C> {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        Self::from_amount(iter.map(|money| money.amount()).sum())
+    }
+}
+
+impl<C: Code> PartialEq for Typed<C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.amount() == other.amount()
+    }
+}
+
+impl<C: Code> PartialOrd for Typed<C> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.amount().partial_cmp(&other.amount())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Dollar, Euro, Mru, Ouguiya};
+    use super::*;
+    use std::time::SystemTime;
+
+    #[test]
+    fn operators() {
+        let mut euros = Euro::from_amount(10.) + Euro::from_amount(2.5);
+        assert_eq!(euros, Euro::from_amount(12.5));
+        assert_eq!(
+            euros.clone() - Euro::from_amount(20.),
+            Euro::from_amount(-7.5)
+        );
+        assert_eq!(-euros.clone(), Euro::from_amount(-12.5));
+        assert_eq!(euros.clone() * 2., Euro::from_amount(25.));
+
+        euros += Euro::from_amount(1.);
+        euros -= Euro::from_amount(0.5);
+        assert_eq!(euros, Euro::from_amount(13.));
+    }
+
+    #[test]
+    fn sum() {
+        let amounts = vec![Dollar::from_amount(1.), Dollar::from_amount(2.)];
+        assert_eq!(amounts.iter().sum::<Dollar>(), Dollar::from_amount(3.));
+        assert_eq!(amounts.into_iter().sum::<Dollar>(), Dollar::from_amount(3.));
+        assert_eq!(
+            Vec::<Dollar>::new().into_iter().sum::<Dollar>(),
+            Dollar::new()
+        );
+    }
+
+    #[test]
+    fn compare() {
+        assert!(Euro::from_amount(1.) < Euro::from_amount(2.));
+        assert!(Euro::from_amount(-1.) <= Euro::from_amount(-1.));
+        assert!(Euro::from_amount(f64::NAN)
+            .partial_cmp(&Euro::from_amount(1.))
+            .is_none());
+    }
+
+    #[test]
+    fn checked() {
+        let max = Dollar::from_amount(f64::MAX);
+        assert!(max.checked_add(&max).is_none());
+        assert!((-max.clone()).checked_sub(&max).is_none());
+        assert!(max.checked_mul(2.).is_none());
+        assert!(Dollar::new().checked_mul(f64::NAN).is_none());
+        assert_eq!(
+            Dollar::from_amount(1.).checked_add(&Dollar::from_amount(2.)),
+            Some(Dollar::from_amount(3.))
+        );
+    }
+
+    #[test]
+    fn convert_to() {
+        let rates = RateTable::current(SystemTime::UNIX_EPOCH);
+        let dollars: Dollar = Euro::from_amount(100.).convert_to(&rates).unwrap();
+        assert!((dollars.amount() - 117.).abs() < 1e-9);
+
+        let mut partial = RateTable::new();
+        partial.set("USD", 1., SystemTime::UNIX_EPOCH);
+        assert!(dollars.convert_to::<Mru>(&partial).is_none());
+        let ouguiyas: Option<Ouguiya> = dollars.convert_to(&rates);
+        assert!((ouguiyas.unwrap().amount() - 3900.).abs() < 1e-6);
+    }
+}
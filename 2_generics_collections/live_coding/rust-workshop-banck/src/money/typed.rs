@@ -0,0 +1,126 @@
+use std::marker::PhantomData;
+
+use super::{standard, Amount, Currency, Money, TypedMoney};
+
+/// A currency known at compile time, by its ISO 4217 code
+pub trait Code {
+    const CODE: &'static str;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Usd;
+impl Code for Usd {
+    const CODE: &'static str = "USD";
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Eur;
+impl Code for Eur {
+    const CODE: &'static str = "EUR";
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mru;
+impl Code for Mru {
+    const CODE: &'static str = "MRU";
+}
+
+/// An amount of money whose currency is part of its type
+#[derive(Debug, Clone)]
+pub struct Typed<C: Code> {
+    amount: Amount,
+    code: PhantomData<C>,
+}
+
+impl<C: Code> Typed<C> {
+    pub const CODE: &'static str = C::CODE;
+
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self::with_amount(0.)
+    }
+
+    /// Create an amount of money from a dollar value
+    pub fn with_amount(amount: f64) -> Self {
+        Self::from_amount(amount / Self::exchange_rate())
+    }
+
+    /// Get the description of the currency of this type
+    pub fn unit() -> &'static Currency {
+        standard()
+            .get(C::CODE)
+            .expect("typed currencies are part of the standard registry")
+    }
+}
+
+impl<C: Code> Money for Typed<C> {
+    fn amount(&self) -> f64 {
+        self.amount.amount()
+    }
+
+    fn value(&self) -> f64 {
+        self.amount.value()
+    }
+
+    fn add(&mut self, other: f64) {
+        self.amount.add(other);
+    }
+
+    fn remove(&mut self, other: f64) {
+        self.amount.remove(other);
+    }
+
+    fn add_amount(&mut self, amount: f64) {
+        self.amount.add_amount(amount);
+    }
+
+    fn remove_amount(&mut self, amount: f64) {
+        self.amount.remove_amount(amount);
+    }
+
+    fn currency(&self) -> &str {
+        C::CODE
+    }
+
+    fn rate(&self) -> f64 {
+        self.amount.rate()
+    }
+}
+
+impl<C: Code> TypedMoney for Typed<C> {
+    fn exchange_rate() -> f64 {
+        Self::unit().rate
+    }
+
+    fn from(other: f64) -> Self {
+        Self::with_amount(other)
+    }
+
+    fn from_amount(amount: f64) -> Self {
+        Self {
+            amount: Amount::new(Self::unit().clone(), amount),
+            code: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Dollar, Euro, Ouguiya};
+    use super::*;
+
+    #[test]
+    fn aliases() {
+        assert_eq!(Dollar::CODE, "USD");
+        assert_eq!(Euro::exchange_rate(), 1.17);
+        assert_eq!(Ouguiya::unit().name, "Ouguiya");
+    }
+
+    #[test]
+    fn with_amount() {
+        let euros = Euro::with_amount(117.);
+        assert!((euros.amount() - 100.).abs() < 1e-9);
+        assert_eq!(euros.currency(), "EUR");
+        assert_eq!(Euro::from_amount(100.).value(), 117.);
+    }
+}
@@ -0,0 +1,346 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    time::SystemTime,
+};
+
+use super::fx::{Conversion, RateTable};
+
+/// The price of a currency pair, such as EUR/USD, as quoted by a market maker
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quote {
+    /// ISO 4217 code of the currency being priced
+    pub base: String,
+    /// ISO 4217 code of the currency the price is given in
+    pub quote: String,
+    /// Amount of `quote` received when selling one unit of `base`
+    pub bid: f64,
+    /// Amount of `quote` paid when buying one unit of `base`
+    pub ask: f64,
+    /// Time at which the price was quoted
+    pub at: SystemTime,
+}
+
+impl Quote {
+    /// Create a quote with a spread between its bid and ask prices
+    pub fn new(base: &str, quote: &str, bid: f64, ask: f64, at: SystemTime) -> Self {
+        Self {
+            base: base.to_string(),
+            quote: quote.to_string(),
+            bid,
+            ask,
+            at,
+        }
+    }
+
+    /// Create a quote without spread
+    pub fn mid(base: &str, quote: &str, rate: f64, at: SystemTime) -> Self {
+        Self::new(base, quote, rate, rate, at)
+    }
+
+    fn is_valid(&self) -> bool {
+        self.base != self.quote && self.bid > 0. && self.bid <= self.ask && self.ask.is_finite()
+    }
+}
+
+/// One conversion of a path through the rate graph
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hop {
+    pub source: String,
+    pub target: String,
+    /// Amount of `target` received for one unit of `source`
+    pub rate: f64,
+    /// Whether the rate was derived from the quote of the inverse pair
+    pub inverted: bool,
+    /// Time at which the quote used was made
+    pub at: SystemTime,
+}
+
+/// The conversions needed to go from one currency to another, and the rates used
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    pub source: String,
+    pub target: String,
+    /// Every conversion made, in order. Empty when converting a currency into itself.
+    pub hops: Vec<Hop>,
+}
+
+impl Path {
+    /// Get the amount of `target` received for one unit of `source`, all hops included
+    pub fn rate(&self) -> f64 {
+        self.hops.iter().map(|hop| hop.rate).product()
+    }
+
+    /// Get the time of the oldest quote used, or `None` for an empty path
+    pub fn quoted_at(&self) -> Option<SystemTime> {
+        self.hops.iter().map(|hop| hop.at).min()
+    }
+
+    /// Get the currencies the money goes through, `source` and `target` included
+    pub fn currencies(&self) -> Vec<&str> {
+        let mut currencies = vec![self.source.as_str()];
+        currencies.extend(self.hops.iter().map(|hop| hop.target.as_str()));
+        currencies
+    }
+
+    /// Convert an amount of money along this path
+    ///
+    /// # Arguments
+    /// * `amount` - an amount of `source`
+    /// * `now` - the time of the conversion, used when the path has no quote
+    pub fn convert(&self, amount: f64, now: SystemTime) -> Conversion {
+        let rate = self.rate();
+        Conversion {
+            source: self.source.clone(),
+            target: self.target.clone(),
+            source_amount: amount,
+            target_amount: amount * rate,
+            rate,
+            rate_at: self.quoted_at().unwrap_or(now),
+        }
+    }
+}
+
+/// Exchange rates between pairs of currencies. Conversions between currencies
+/// without a quote go through the fewest intermediate currencies possible.
+#[derive(Debug, Clone, Default)]
+pub struct RateGraph {
+    /// Quotes indexed by base, then by quote currency
+    quotes: BTreeMap<String, BTreeMap<String, Quote>>,
+}
+
+impl RateGraph {
+    /// Create a graph without any quote
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a quote to the graph, replacing the one of the same pair if any.
+    ///
+    /// # Returns
+    /// `false` if the quote was not added because its prices are not positive, its
+    /// bid is above its ask, or its base and quote currencies are the same.
+    pub fn quote(&mut self, quote: Quote) -> bool {
+        if !quote.is_valid() {
+            return false;
+        }
+        self.quotes
+            .entry(quote.base.clone())
+            .or_default()
+            .insert(quote.quote.clone(), quote);
+        true
+    }
+
+    /// Get the quote of a pair, if one was made
+    pub fn get(&self, base: &str, quote: &str) -> Option<&Quote> {
+        self.quotes.get(base)?.get(quote)
+    }
+
+    /// Find how to convert `source` into `target`.
+    ///
+    /// A direct quote of the pair is used first: its bid price, as `source` is sold.
+    /// The quote of the inverse pair comes next: `target` is bought at its ask price.
+    /// Otherwise, the conversion is triangulated through the path with the fewest
+    /// hops, ties being broken by the alphabetical order of the currencies.
+    ///
+    /// # Returns
+    /// The path to follow, or `None` if the currencies are not connected.
+    pub fn path(&self, source: &str, target: &str) -> Option<Path> {
+        let mut previous: HashMap<String, Hop> = HashMap::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(source.to_string());
+
+        while let Some(currency) = queue.pop_front() {
+            if currency == target {
+                break;
+            }
+            for hop in self.hops(&currency) {
+                if hop.target != source && !previous.contains_key(&hop.target) {
+                    queue.push_back(hop.target.clone());
+                    previous.insert(hop.target.clone(), hop);
+                }
+            }
+        }
+
+        let mut hops = Vec::new();
+        let mut currency = target.to_string();
+        while currency != source {
+            let hop = previous.remove(&currency)?;
+            currency = hop.source.clone();
+            hops.push(hop);
+        }
+        hops.reverse();
+
+        Some(Path {
+            source: source.to_string(),
+            target: target.to_string(),
+            hops,
+        })
+    }
+
+    /// Convert an amount of money, as `path` would
+    ///
+    /// # Returns
+    /// The conversion made and the path followed, or `None` if the currencies are
+    /// not connected.
+    pub fn convert(
+        &self,
+        source: &str,
+        target: &str,
+        amount: f64,
+        now: SystemTime,
+    ) -> Option<(Conversion, Path)> {
+        let path = self.path(source, target)?;
+        Some((path.convert(amount, now), path))
+    }
+
+    /// Get every conversion possible from `source` in one hop, by target currency.
+    /// A direct quote wins over the inverse of another.
+    fn hops(&self, source: &str) -> Vec<Hop> {
+        let mut hops: BTreeMap<&str, Hop> = BTreeMap::new();
+        for (base, quotes) in &self.quotes {
+            if let Some(quote) = quotes.get(source) {
+                hops.insert(
+                    base,
+                    Hop {
+                        source: source.to_string(),
+                        target: base.clone(),
+                        rate: 1. / quote.ask,
+                        inverted: true,
+                        at: quote.at,
+                    },
+                );
+            }
+        }
+        for (target, quote) in self.quotes.get(source).into_iter().flatten() {
+            hops.insert(
+                target,
+                Hop {
+                    source: source.to_string(),
+                    target: target.clone(),
+                    rate: quote.bid,
+                    inverted: false,
+                    at: quote.at,
+                },
+            );
+        }
+        hops.into_values().collect()
+    }
+}
+
+impl From<&RateTable> for RateGraph {
+    /// Quote every currency of the table against the dollar
+    fn from(table: &RateTable) -> Self {
+        let mut graph = Self::new();
+        for (currency, rate) in table.iter() {
+            graph.quote(Quote::mid(currency, "USD", rate.dollars, rate.at));
+        }
+        graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn at(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn invalid_quotes() {
+        let mut graph = RateGraph::new();
+        assert!(!graph.quote(Quote::new("EUR", "USD", 1.2, 1.1, at(0))));
+        assert!(!graph.quote(Quote::mid("EUR", "USD", 0., at(0))));
+        assert!(!graph.quote(Quote::mid("EUR", "EUR", 1., at(0))));
+        assert!(graph.quote(Quote::new("EUR", "USD", 1.1, 1.2, at(0))));
+        assert_eq!(graph.get("EUR", "USD").unwrap().ask, 1.2);
+        assert!(graph.get("USD", "EUR").is_none());
+    }
+
+    #[test]
+    fn direct_and_inverse() {
+        let mut graph = RateGraph::new();
+        graph.quote(Quote::new("EUR", "USD", 1.16, 1.18, at(1)));
+
+        let path = graph.path("EUR", "USD").unwrap();
+        assert_eq!(path.rate(), 1.16);
+        assert!(!path.hops[0].inverted);
+        assert_eq!(path.quoted_at(), Some(at(1)));
+
+        let path = graph.path("USD", "EUR").unwrap();
+        assert_eq!(path.rate(), 1. / 1.18);
+        assert!(path.hops[0].inverted);
+
+        // A direct quote is preferred over the inverse of the other pair
+        graph.quote(Quote::mid("USD", "EUR", 0.86, at(2)));
+        let path = graph.path("USD", "EUR").unwrap();
+        assert_eq!(path.rate(), 0.86);
+        assert!(!path.hops[0].inverted);
+    }
+
+    #[test]
+    fn same_currency() {
+        let path = RateGraph::new().path("EUR", "EUR").unwrap();
+        assert!(path.hops.is_empty());
+        assert_eq!(path.rate(), 1.);
+        assert_eq!(path.convert(10., at(5)).rate_at, at(5));
+    }
+
+    #[test]
+    fn triangulation() {
+        let mut graph = RateGraph::new();
+        graph.quote(Quote::mid("EUR", "USD", 1.2, at(3)));
+        graph.quote(Quote::mid("USD", "MRU", 40., at(1)));
+        graph.quote(Quote::mid("GBP", "EUR", 1.1, at(2)));
+        graph.quote(Quote::mid("GBP", "JPY", 150., at(2)));
+        graph.quote(Quote::mid("JPY", "MRU", 0.25, at(2)));
+
+        let path = graph.path("EUR", "MRU").unwrap();
+        assert_eq!(path.currencies(), vec!["EUR", "USD", "MRU"]);
+        assert_eq!(path.rate(), 1.2 * 40.);
+        assert_eq!(path.quoted_at(), Some(at(1)));
+
+        // GBP -> EUR -> USD wins over GBP -> JPY -> MRU -> USD
+        let path = graph.path("GBP", "USD").unwrap();
+        assert_eq!(path.currencies(), vec!["GBP", "EUR", "USD"]);
+
+        let path = graph.path("MRU", "GBP").unwrap();
+        assert_eq!(path.currencies(), vec!["MRU", "JPY", "GBP"]);
+        assert!(path.hops.iter().all(|hop| hop.inverted));
+        assert!((path.rate() - 1. / (0.25 * 150.)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn not_connected() {
+        let mut graph = RateGraph::new();
+        graph.quote(Quote::mid("EUR", "USD", 1.2, at(0)));
+        graph.quote(Quote::mid("GBP", "JPY", 150., at(0)));
+
+        assert!(graph.path("EUR", "JPY").is_none());
+        assert!(graph.path("EUR", "XXX").is_none());
+        assert!(graph.convert("USD", "GBP", 1., at(0)).is_none());
+    }
+
+    #[test]
+    fn convert() {
+        let mut graph = RateGraph::new();
+        graph.quote(Quote::mid("EUR", "MRU", 39., at(7)));
+
+        let (conversion, path) = graph.convert("EUR", "MRU", 100., at(9)).unwrap();
+        assert_eq!(conversion.target_amount, 3900.);
+        assert_eq!(conversion.rate, 39.);
+        assert_eq!(conversion.rate_at, at(7));
+        assert_eq!(path.hops.len(), 1);
+    }
+
+    #[test]
+    fn from_table() {
+        let graph = RateGraph::from(&RateTable::current(at(0)));
+
+        assert_eq!(graph.get("EUR", "USD").unwrap().bid, 1.17);
+        let path = graph.path("EUR", "MRU").unwrap();
+        assert_eq!(path.currencies(), vec!["EUR", "USD", "MRU"]);
+        assert!((path.rate() - 1.17 / 0.03).abs() < 1e-9);
+    }
+}
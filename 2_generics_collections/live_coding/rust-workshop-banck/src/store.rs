@@ -0,0 +1,1329 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::TryInto,
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+use uuid::Uuid;
+
+use super::account::Account;
+use super::bank::*;
+use super::budget::{Budget, Limit, LimitExceeded};
+use super::clock::{Clock, ManualClock, SystemClock};
+use super::fx::Conversion;
+use super::history::{Lookup, RateError, RateHistory};
+use super::idempotency::*;
+use super::ledger::{Ledger, LedgerError};
+use super::money::from_currency;
+
+/// Name of the write-ahead log inside the store directory
+pub const LOG_FILE: &str = "bank.wal";
+/// Name of the snapshot inside the store directory
+pub const SNAPSHOT_FILE: &str = "bank.snapshot";
+const SNAPSHOT_TMP_FILE: &str = "bank.snapshot.tmp";
+
+/// Default number of logged operations after which a snapshot is taken
+pub const DEFAULT_SNAPSHOT_INTERVAL: u64 = 1000;
+
+/// Size of a record header: the payload length then its CRC32, both little endian `u32`
+const HEADER_SIZE: usize = 8;
+
+#[derive(Debug)]
+pub enum StoreError {
+    /// The store could not be read or written
+    Io(io::Error),
+    /// A snapshot or a log record could not be (de)serialized
+    Format(serde_json::Error),
+    /// A persisted account uses a currency this banck does not know
+    UnknownCurrency(String),
+    NoSuchAccount(NoSuchAccountError),
+    LimitExceeded(LimitExceeded),
+    ReusedRequestId(Uuid),
+    /// A record in the middle of the log is corrupted, at this offset: the records
+    /// after it cannot be trusted
+    CorruptedLog(u64),
+    /// Writing to the log failed and the partial record could not be cut off, so
+    /// nothing can be logged until a snapshot empties the log
+    Poisoned,
+    /// No rate of the currency is known at the time of a conversion
+    NoRate {
+        currency: String,
+        as_of: SystemTime,
+    },
+    /// The ledger of the banck refused to record an operation
+    Ledger(LedgerError),
+}
+
+impl From<io::Error> for StoreError {
+    fn from(err: io::Error) -> Self {
+        StoreError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for StoreError {
+    fn from(err: serde_json::Error) -> Self {
+        StoreError::Format(err)
+    }
+}
+
+impl From<NoSuchAccountError> for StoreError {
+    fn from(err: NoSuchAccountError) -> Self {
+        StoreError::NoSuchAccount(err)
+    }
+}
+
+impl From<LedgerError> for StoreError {
+    fn from(err: LedgerError) -> Self {
+        StoreError::Ledger(err)
+    }
+}
+
+impl From<MovementError> for StoreError {
+    fn from(err: MovementError) -> Self {
+        match err {
+            MovementError::NoSuchAccount(err) => StoreError::NoSuchAccount(err),
+            MovementError::Ledger(err) => StoreError::Ledger(err),
+        }
+    }
+}
+
+impl From<WithdrawalError> for StoreError {
+    fn from(err: WithdrawalError) -> Self {
+        match err {
+            WithdrawalError::NoSuchAccount(err) => StoreError::NoSuchAccount(err),
+            WithdrawalError::LimitExceeded(exceeded) => StoreError::LimitExceeded(exceeded),
+            WithdrawalError::Ledger(err) => StoreError::Ledger(err),
+        }
+    }
+}
+
+impl From<RateError> for StoreError {
+    fn from(err: RateError) -> Self {
+        match err {
+            RateError::NoSuchAccount(err) => StoreError::NoSuchAccount(err),
+            RateError::NoRate { currency, as_of } => StoreError::NoRate { currency, as_of },
+            RateError::Ledger(err) => StoreError::Ledger(err),
+        }
+    }
+}
+
+impl From<RequestError> for StoreError {
+    fn from(err: RequestError) -> Self {
+        match err {
+            RequestError::NoSuchAccount(err) => StoreError::NoSuchAccount(err),
+            RequestError::LimitExceeded(exceeded) => StoreError::LimitExceeded(exceeded),
+            RequestError::ReusedRequestId(id) => StoreError::ReusedRequestId(id),
+            RequestError::Ledger(err) => StoreError::Ledger(err),
+        }
+    }
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Io(err) => write!(f, "store i/o error: {}", err),
+            StoreError::Format(err) => write!(f, "invalid store content: {}", err),
+            StoreError::UnknownCurrency(code) => write!(f, "unknown currency: {}", code),
+            StoreError::CorruptedLog(offset) => {
+                write!(f, "corrupted log record at byte {}", offset)
+            }
+            StoreError::Poisoned => write!(f, "the log holds a partial record"),
+            StoreError::NoSuchAccount(err) => err.fmt(f),
+            StoreError::LimitExceeded(exceeded) => exceeded.fmt(f),
+            StoreError::ReusedRequestId(id) => RequestError::ReusedRequestId(*id).fmt(f),
+            StoreError::NoRate { currency, as_of } => RateError::NoRate {
+                currency: currency.clone(),
+                as_of: *as_of,
+            }
+            .fmt(f),
+            StoreError::Ledger(err) => err.fmt(f),
+        }
+    }
+}
+
+/// A mutation of the banck, as written in the log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum Operation {
+    AddAccount(AccountState),
+    Deposit {
+        request: Option<Uuid>,
+        account: Uuid,
+        amount: f64,
+        /// Time of the rate the money was converted with, if not the current one
+        #[serde(default)]
+        as_of: Option<SystemTime>,
+    },
+    Withdrawal {
+        request: Option<Uuid>,
+        account: Uuid,
+        amount: f64,
+        #[serde(default)]
+        category: Option<String>,
+        /// Time of the rate the money was converted with, if not the current one
+        #[serde(default)]
+        as_of: Option<SystemTime>,
+    },
+    Transfer {
+        request: Option<Uuid>,
+        from: Uuid,
+        to: Uuid,
+        amount: f64,
+    },
+    Rename {
+        account: Uuid,
+        name: String,
+    },
+    SetSpendingLimit {
+        account: Uuid,
+        category: Option<String>,
+        limit: Option<Limit>,
+    },
+    SetIdempotencyWindow {
+        window: Duration,
+    },
+    ChargeFee {
+        account: Uuid,
+        amount: f64,
+    },
+    EnableDoubleEntry,
+    SetRateHistory {
+        history: RateHistory,
+        lookup: Lookup,
+    },
+}
+
+/// A record of the log
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    /// Sequence number of the operation, starting at 1
+    seq: u64,
+    /// Time at which the operation was acknowledged, used to replay idempotent requests
+    at: SystemTime,
+    operation: Operation,
+}
+
+/// Everything needed to rebuild an account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AccountState {
+    id: Uuid,
+    name: String,
+    currency: String,
+    /// Amount of money in the currency of the account
+    amount: f64,
+    #[serde(default)]
+    conversions: Vec<Conversion>,
+    #[serde(default)]
+    budget: Budget,
+}
+
+impl AccountState {
+    fn of(account: &Account) -> Self {
+        Self {
+            id: *account.get_id(),
+            name: account.get_name().to_string(),
+            currency: account.get_currency().to_string(),
+            amount: account.get_amount(),
+            conversions: account.get_conversions().to_vec(),
+            budget: account.get_budget().clone(),
+        }
+    }
+
+    fn restore<'a>(self) -> Result<Account<'a>, StoreError> {
+        let money = from_currency(&self.currency, self.amount)
+            .ok_or(StoreError::UnknownCurrency(self.currency))?;
+        Ok(Account::with_money(&self.name, &self.id, money)
+            .with_conversions(self.conversions)
+            .with_budget(self.budget))
+    }
+}
+
+fn default_window() -> Duration {
+    DEFAULT_WINDOW
+}
+
+fn default_lookup() -> Lookup {
+    Lookup::Previous
+}
+
+/// The whole state of a banck after the operation `seq`
+#[derive(Debug, Serialize, Deserialize)]
+struct Snapshot {
+    seq: u64,
+    accounts: Vec<AccountState>,
+    #[serde(default = "default_window")]
+    idempotency_window: Duration,
+    requests: Vec<(Uuid, Record)>,
+    /// The double-entry ledger, if it was enabled
+    #[serde(default)]
+    ledger: Option<Ledger>,
+    #[serde(default)]
+    rate_history: RateHistory,
+    #[serde(default = "default_lookup")]
+    rate_lookup: Lookup,
+}
+
+impl Snapshot {
+    fn of(bank: &Bank, seq: u64) -> Self {
+        Self {
+            seq,
+            accounts: bank.accounts().map(AccountState::of).collect(),
+            idempotency_window: bank.requests().window(),
+            requests: bank
+                .requests()
+                .records()
+                .map(|(id, record)| (*id, record.clone()))
+                .collect(),
+            ledger: bank.ledger().cloned(),
+            rate_history: bank.rate_history().clone(),
+            rate_lookup: bank.rate_lookup(),
+        }
+    }
+
+    fn restore<'a>(self) -> Result<Bank<'a>, StoreError> {
+        let mut bank = Bank::with_idempotency_window(self.idempotency_window);
+        bank.set_rate_history(self.rate_history, self.rate_lookup);
+
+        for account in self.accounts {
+            bank.add_account(account.restore()?)?;
+        }
+        for (id, record) in self.requests {
+            bank.requests_mut().insert_record(&id, record);
+        }
+        // Set after the accounts were added, which are already in the ledger
+        bank.set_ledger(self.ledger);
+
+        Ok(bank)
+    }
+}
+
+/// A banck persisted in a directory.
+///
+/// Every mutation is appended to a write-ahead log and flushed to disk before being
+/// applied, so that an acknowledged operation survives a crash. Every
+/// `snapshot_interval` operations, the whole banck is written to a snapshot and the
+/// log is emptied.
+///
+/// Operations are applied at the time they were logged: the clock of the banck is
+/// driven by the store, which dates new operations with its own clock.
+pub struct Store<'a> {
+    dir: PathBuf,
+    bank: Bank<'a>,
+    /// Clock of the banck, set to the time of each operation it applies
+    clock: ManualClock,
+    /// Clock dating the operations as they are logged
+    source: Box<dyn Clock + 'a>,
+    log: File,
+    /// Sequence number of the last logged operation
+    seq: u64,
+    /// Number of operations logged since the last snapshot
+    pending: u64,
+    snapshot_interval: u64,
+    /// A failed write left a partial record at the end of the log
+    poisoned: bool,
+}
+
+impl<'a> Store<'a> {
+    /// Open the store located in `dir`, creating it if needed.
+    ///
+    /// The banck is recovered from the last snapshot, then every operation of the log
+    /// which is not part of the snapshot is replayed. A torn record at the end of the
+    /// log, left by a crash in the middle of a write, is detected and cut off. A
+    /// corrupted record followed by others is reported as `CorruptedLog` instead.
+    ///
+    /// # Arguments
+    /// * `dir` - The directory holding the snapshot and the log.
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self, StoreError> {
+        Self::open_with_clock(dir, SystemClock)
+    }
+
+    /// Open the store located in `dir` like `open`, dating new operations with
+    /// `clock` instead of the clock of the system.
+    ///
+    /// # Arguments
+    /// * `dir` - The directory holding the snapshot and the log.
+    /// * `clock` - The clock giving the time of each new operation, which decides
+    ///   for example the month a withdrawal counts in.
+    pub fn open_with_clock<P: AsRef<Path>, C: Clock + 'a>(
+        dir: P,
+        clock: C,
+    ) -> Result<Self, StoreError> {
+        let source: Box<dyn Clock + 'a> = Box::new(clock);
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        let (mut bank, snapshot_seq) = match fs::read(dir.join(SNAPSHOT_FILE)) {
+            Ok(bytes) => {
+                let snapshot: Snapshot = serde_json::from_slice(&bytes)?;
+                let seq = snapshot.seq;
+                (snapshot.restore()?, seq)
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (Bank::new(), 0),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut log = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(dir.join(LOG_FILE))?;
+        let (entries, valid_len) = read_log(&mut log)?;
+        if valid_len < log.metadata()?.len() {
+            log.set_len(valid_len)?;
+            log.sync_all()?;
+        }
+
+        let clock = ManualClock::new(source.now());
+        bank.set_clock(clock.clone());
+
+        let mut seq = snapshot_seq;
+        let mut pending = 0;
+        // The log may still hold operations already in the snapshot if we crashed
+        // before it could be emptied
+        for entry in entries.iter().filter(|entry| entry.seq > snapshot_seq) {
+            clock.set(entry.at);
+            replay(&mut bank, entry)?;
+            seq = entry.seq;
+            pending += 1;
+        }
+
+        Ok(Self {
+            dir,
+            bank,
+            clock,
+            source,
+            log,
+            seq,
+            pending,
+            snapshot_interval: DEFAULT_SNAPSHOT_INTERVAL,
+            poisoned: false,
+        })
+    }
+
+    /// Get the banck held by this store
+    pub fn bank(&self) -> &Bank<'a> {
+        &self.bank
+    }
+
+    /// Change the number of operations after which a snapshot is taken
+    pub fn set_snapshot_interval(&mut self, interval: u64) {
+        self.snapshot_interval = interval;
+    }
+
+    /// Change the amount of time during which idempotent requests are remembered.
+    /// The change is logged, so that requests are replayed with the window they
+    /// were first applied with.
+    pub fn set_idempotency_window(&mut self, window: Duration) -> Result<(), StoreError> {
+        self.log(Operation::SetIdempotencyWindow { window })?;
+        self.bank.set_idempotency_window(window);
+        Ok(())
+    }
+
+    /// Write the whole banck to the snapshot and empty the log.
+    pub fn snapshot(&mut self) -> Result<(), StoreError> {
+        let snapshot = serde_json::to_vec(&Snapshot::of(&self.bank, self.seq))?;
+
+        let tmp = self.dir.join(SNAPSHOT_TMP_FILE);
+        let mut file = File::create(&tmp)?;
+        file.write_all(&snapshot)?;
+        file.sync_all()?;
+        fs::rename(&tmp, self.dir.join(SNAPSHOT_FILE))?;
+        // Make the rename itself durable
+        File::open(&self.dir)?.sync_all()?;
+
+        self.log.set_len(0)?;
+        self.log.sync_all()?;
+        self.pending = 0;
+        self.poisoned = false;
+
+        Ok(())
+    }
+
+    /// Add a new account to the banck, with its conversions and budget.
+    /// See `Bank::add_account`.
+    pub fn add_account(&mut self, account: Account<'a>) -> Result<(), StoreError> {
+        self.log(Operation::AddAccount(AccountState::of(&account)))?;
+        Ok(self.bank.add_account(account)?)
+    }
+
+    /// Add money to the given account. See `Bank::add_account_money`.
+    pub fn add_account_money(&mut self, account_id: &Uuid, amount: f64) -> Result<(), StoreError> {
+        self.log(Operation::Deposit {
+            request: None,
+            account: *account_id,
+            amount,
+            as_of: None,
+        })?;
+        Ok(self.bank.add_account_money(account_id, amount)?)
+    }
+
+    /// Add money to the given account, converted at a past rate.
+    /// See `Bank::add_account_money_as_of`.
+    pub fn add_account_money_as_of(
+        &mut self,
+        account_id: &Uuid,
+        amount: f64,
+        as_of: SystemTime,
+    ) -> Result<(), StoreError> {
+        self.log(Operation::Deposit {
+            request: None,
+            account: *account_id,
+            amount,
+            as_of: Some(as_of),
+        })?;
+        Ok(self
+            .bank
+            .add_account_money_as_of(account_id, amount, as_of)?)
+    }
+
+    /// Retrieve money from the given account. See `Bank::retrieve_account_money`.
+    pub fn retrieve_account_money(
+        &mut self,
+        account_id: &Uuid,
+        amount: f64,
+    ) -> Result<(f64, Vec<LimitExceeded>), StoreError> {
+        self.retrieve_account_money_for(account_id, amount, None)
+    }
+
+    /// Retrieve money spent on a category from the given account.
+    /// See `Bank::retrieve_account_money_for`.
+    pub fn retrieve_account_money_for(
+        &mut self,
+        account_id: &Uuid,
+        amount: f64,
+        category: Option<&str>,
+    ) -> Result<(f64, Vec<LimitExceeded>), StoreError> {
+        self.log(Operation::Withdrawal {
+            request: None,
+            account: *account_id,
+            amount,
+            category: category.map(str::to_string),
+            as_of: None,
+        })?;
+        Ok(self
+            .bank
+            .retrieve_account_money_for(account_id, amount, category)?)
+    }
+
+    /// Retrieve money from the given account, converted at a past rate.
+    /// See `Bank::retrieve_account_money_as_of`.
+    pub fn retrieve_account_money_as_of(
+        &mut self,
+        account_id: &Uuid,
+        amount: f64,
+        as_of: SystemTime,
+    ) -> Result<f64, StoreError> {
+        self.log(Operation::Withdrawal {
+            request: None,
+            account: *account_id,
+            amount,
+            category: None,
+            as_of: Some(as_of),
+        })?;
+        Ok(self
+            .bank
+            .retrieve_account_money_as_of(account_id, amount, as_of)?)
+    }
+
+    /// Move money from one account to another. See `Bank::transfer`.
+    pub fn transfer(&mut self, from: &Uuid, to: &Uuid, amount: f64) -> Result<f64, StoreError> {
+        self.log(Operation::Transfer {
+            request: None,
+            from: *from,
+            to: *to,
+            amount,
+        })?;
+        Ok(self.bank.transfer(from, to, amount)?)
+    }
+
+    /// Charge a fee to the given account. See `Bank::charge_fee`.
+    pub fn charge_fee(&mut self, account_id: &Uuid, amount: f64) -> Result<f64, StoreError> {
+        self.log(Operation::ChargeFee {
+            account: *account_id,
+            amount,
+        })?;
+        Ok(self.bank.charge_fee(account_id, amount)?)
+    }
+
+    /// Change the past exchange rates, which are logged whole.
+    /// See `Bank::set_rate_history`.
+    pub fn set_rate_history(
+        &mut self,
+        history: RateHistory,
+        lookup: Lookup,
+    ) -> Result<(), StoreError> {
+        self.log(Operation::SetRateHistory {
+            history: history.clone(),
+            lookup,
+        })?;
+        self.bank.set_rate_history(history, lookup);
+        Ok(())
+    }
+
+    /// Start keeping a double-entry ledger. See `Bank::enable_double_entry`.
+    pub fn enable_double_entry(&mut self) -> Result<(), StoreError> {
+        self.log(Operation::EnableDoubleEntry)?;
+        Ok(self.bank.enable_double_entry()?)
+    }
+
+    /// Rename the account. See `Bank::rename_account`.
+    pub fn rename_account(&mut self, account_id: &Uuid, new_name: &str) -> Result<(), StoreError> {
+        self.log(Operation::Rename {
+            account: *account_id,
+            name: new_name.to_string(),
+        })?;
+        Ok(self.bank.rename_account(account_id, new_name)?)
+    }
+
+    /// Set, or remove, a spending limit of the given account.
+    /// See `Bank::set_spending_limit`.
+    pub fn set_spending_limit(
+        &mut self,
+        account_id: &Uuid,
+        category: Option<&str>,
+        limit: Option<Limit>,
+    ) -> Result<(), StoreError> {
+        self.log(Operation::SetSpendingLimit {
+            account: *account_id,
+            category: category.map(str::to_string),
+            limit,
+        })?;
+        Ok(self.bank.set_spending_limit(account_id, category, limit)?)
+    }
+
+    /// Idempotent deposit. See `Bank::add_account_money_idempotent`.
+    pub fn add_account_money_idempotent(
+        &mut self,
+        request_id: &Uuid,
+        account_id: &Uuid,
+        amount: f64,
+    ) -> Result<(), StoreError> {
+        self.log(Operation::Deposit {
+            request: Some(*request_id),
+            account: *account_id,
+            amount,
+            as_of: None,
+        })?;
+        Ok(self
+            .bank
+            .add_account_money_idempotent(request_id, account_id, amount)?)
+    }
+
+    /// Idempotent withdrawal. See `Bank::retrieve_account_money_idempotent`.
+    pub fn retrieve_account_money_idempotent(
+        &mut self,
+        request_id: &Uuid,
+        account_id: &Uuid,
+        amount: f64,
+    ) -> Result<f64, StoreError> {
+        self.log(Operation::Withdrawal {
+            request: Some(*request_id),
+            account: *account_id,
+            amount,
+            category: None,
+            as_of: None,
+        })?;
+        Ok(self
+            .bank
+            .retrieve_account_money_idempotent(request_id, account_id, amount)?)
+    }
+
+    /// Idempotent transfer. See `Bank::transfer_idempotent`.
+    pub fn transfer_idempotent(
+        &mut self,
+        request_id: &Uuid,
+        from: &Uuid,
+        to: &Uuid,
+        amount: f64,
+    ) -> Result<f64, StoreError> {
+        self.log(Operation::Transfer {
+            request: Some(*request_id),
+            from: *from,
+            to: *to,
+            amount,
+        })?;
+        Ok(self
+            .bank
+            .transfer_idempotent(request_id, from, to, amount)?)
+    }
+
+    /// Append an operation to the log and flush it to disk, taking a snapshot first
+    /// if enough operations were logged since the last one. The clock of the banck
+    /// is then set to the time at which the operation was logged.
+    ///
+    /// If the record cannot be written whole, it is cut off the log, so that the next
+    /// records do not follow a torn one. The store is poisoned if even that fails.
+    fn log(&mut self, operation: Operation) -> Result<(), StoreError> {
+        if self.poisoned {
+            return Err(StoreError::Poisoned);
+        }
+        if self.pending >= self.snapshot_interval {
+            self.snapshot()?;
+        }
+
+        let entry = Entry {
+            seq: self.seq + 1,
+            at: self.source.now(),
+            operation,
+        };
+        let payload = serde_json::to_vec(&entry)?;
+        let mut record = Vec::with_capacity(HEADER_SIZE + payload.len());
+        record.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        record.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+        record.extend_from_slice(&payload);
+
+        let len = self.log.metadata()?.len();
+        let written = self
+            .log
+            .write_all(&record)
+            .and_then(|()| self.log.sync_data());
+        if let Err(err) = written {
+            if self.log.set_len(len).is_err() {
+                self.poisoned = true;
+            }
+            return Err(err.into());
+        }
+        self.seq = entry.seq;
+        self.pending += 1;
+        self.clock.set(entry.at);
+
+        Ok(())
+    }
+}
+
+/// Read every valid record of the log.
+///
+/// # Returns
+/// The records, and the length of the log up to the end of the last valid one, or
+/// an error if an invalid record is followed by more data: only the last record can
+/// be torn by a crash.
+fn read_log(log: &mut File) -> Result<(Vec<Entry>, u64), StoreError> {
+    let mut bytes = Vec::new();
+    log.seek(SeekFrom::Start(0))?;
+    log.read_to_end(&mut bytes)?;
+
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    loop {
+        match decode(&bytes[offset..]) {
+            Decoded::Valid(entry, size) => {
+                entries.push(*entry);
+                offset += size;
+            }
+            Decoded::Invalid(size) if offset + size < bytes.len() => {
+                return Err(StoreError::CorruptedLog(offset as u64));
+            }
+            Decoded::Invalid(_) | Decoded::Incomplete => break,
+        }
+    }
+
+    Ok((entries, offset as u64))
+}
+
+/// A record decoded from the log
+enum Decoded {
+    /// A valid record, and its size
+    Valid(Box<Entry>, usize),
+    /// A whole record which is corrupted, and its size
+    Invalid(usize),
+    /// The log ends in the middle of the record
+    Incomplete,
+}
+
+/// Decode the record at the start of `bytes`.
+fn decode(bytes: &[u8]) -> Decoded {
+    let header = match bytes.get(..HEADER_SIZE) {
+        Some(header) => header,
+        None => return Decoded::Incomplete,
+    };
+    // The header is exactly 8 bytes, so both halves are 4 bytes long
+    let len = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+    let checksum = u32::from_le_bytes(header[4..].try_into().unwrap());
+
+    let payload = match bytes.get(HEADER_SIZE..HEADER_SIZE + len) {
+        Some(payload) => payload,
+        None => return Decoded::Incomplete,
+    };
+    if crc32fast::hash(payload) != checksum {
+        return Decoded::Invalid(HEADER_SIZE + len);
+    }
+    match serde_json::from_slice(payload) {
+        Ok(entry) => Decoded::Valid(Box::new(entry), HEADER_SIZE + len),
+        Err(_) => Decoded::Invalid(HEADER_SIZE + len),
+    }
+}
+
+/// Apply a logged operation to the banck again. Operations which failed when they
+/// were first applied fail the same way, and their error is ignored.
+fn replay(bank: &mut Bank, entry: &Entry) -> Result<(), StoreError> {
+    match &entry.operation {
+        Operation::AddAccount(account) => {
+            let _ = bank.add_account(account.clone().restore()?);
+        }
+        Operation::Deposit {
+            request: None,
+            account,
+            amount,
+            as_of: None,
+        } => {
+            let _ = bank.add_account_money(account, *amount);
+        }
+        Operation::Deposit {
+            request: None,
+            account,
+            amount,
+            as_of: Some(as_of),
+        } => {
+            let _ = bank.add_account_money_as_of(account, *amount, *as_of);
+        }
+        Operation::Deposit {
+            request: Some(request),
+            account,
+            amount,
+            ..
+        } => {
+            let _ = bank.add_account_money_idempotent(request, account, *amount);
+        }
+        Operation::Withdrawal {
+            request: None,
+            account,
+            amount,
+            category,
+            as_of: None,
+        } => {
+            let _ = bank.retrieve_account_money_for(account, *amount, category.as_deref());
+        }
+        Operation::Withdrawal {
+            request: None,
+            account,
+            amount,
+            as_of: Some(as_of),
+            ..
+        } => {
+            let _ = bank.retrieve_account_money_as_of(account, *amount, *as_of);
+        }
+        Operation::Withdrawal {
+            request: Some(request),
+            account,
+            amount,
+            ..
+        } => {
+            let _ = bank.retrieve_account_money_idempotent(request, account, *amount);
+        }
+        Operation::Transfer {
+            request: None,
+            from,
+            to,
+            amount,
+        } => {
+            let _ = bank.transfer(from, to, *amount);
+        }
+        Operation::Transfer {
+            request: Some(request),
+            from,
+            to,
+            amount,
+        } => {
+            let _ = bank.transfer_idempotent(request, from, to, *amount);
+        }
+        Operation::Rename { account, name } => {
+            let _ = bank.rename_account(account, name);
+        }
+        Operation::SetSpendingLimit {
+            account,
+            category,
+            limit,
+        } => {
+            let _ = bank.set_spending_limit(account, category.as_deref(), *limit);
+        }
+        Operation::SetIdempotencyWindow { window } => {
+            bank.set_idempotency_window(*window);
+        }
+        Operation::ChargeFee { account, amount } => {
+            let _ = bank.charge_fee(account, *amount);
+        }
+        Operation::EnableDoubleEntry => {
+            let _ = bank.enable_double_entry();
+        }
+        Operation::SetRateHistory { history, lookup } => {
+            bank.set_rate_history(history.clone(), *lookup);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::budget::Enforcement;
+    use super::super::calendar::date;
+    use super::super::ledger::FEES;
+    use super::super::money::*;
+    use super::*;
+    use tempfile::tempdir;
+
+    type Step<'a> = Box<dyn Fn(&mut Store) + 'a>;
+
+    /// Every account of the banck as `(id, name, amount)`, sorted by id
+    fn accounts(bank: &Bank) -> Vec<(Uuid, String, f64)> {
+        let mut accounts: Vec<_> = bank
+            .accounts()
+            .map(|account| {
+                (
+                    *account.get_id(),
+                    account.get_name().to_string(),
+                    account.get_amount(),
+                )
+            })
+            .collect();
+        accounts.sort_by_key(|(id, _, _)| *id);
+        accounts
+    }
+
+    fn log_len(dir: &Path) -> u64 {
+        fs::metadata(dir.join(LOG_FILE)).unwrap().len()
+    }
+
+    #[test]
+    fn reopen() {
+        let dir = tempdir().unwrap();
+        let id = Uuid::new_v4();
+        {
+            let mut store = Store::open(dir.path()).unwrap();
+            store
+                .add_account(Account::with_id::<Euro>("account", &id))
+                .unwrap();
+            store.add_account_money(&id, 100.).unwrap();
+            store.rename_account(&id, "renamed").unwrap();
+        }
+
+        let store = Store::open(dir.path()).unwrap();
+        let account = store.bank().get_account(&id).unwrap();
+        assert_eq!(account.get_name(), "renamed");
+        assert_eq!(account.get_currency(), "EUR");
+        assert_eq!(account.get_value(), 100.);
+    }
+
+    #[test]
+    fn errors_are_reported() {
+        let dir = tempdir().unwrap();
+        let mut store = Store::open(dir.path()).unwrap();
+
+        assert!(matches!(
+            store.add_account_money(&Uuid::new_v4(), 10.),
+            Err(StoreError::NoSuchAccount(_))
+        ));
+    }
+
+    #[test]
+    fn snapshot_compacts_log() {
+        let dir = tempdir().unwrap();
+        let id = Uuid::new_v4();
+        let expected = {
+            let mut store = Store::open(dir.path()).unwrap();
+            store.set_snapshot_interval(2);
+            store
+                .add_account(Account::with_id::<Dollar>("account", &id))
+                .unwrap();
+            for _ in 0..5 {
+                store.add_account_money(&id, 3.).unwrap();
+            }
+            accounts(store.bank())
+        };
+
+        assert!(dir.path().join(SNAPSHOT_FILE).exists());
+        let store = Store::open(dir.path()).unwrap();
+        assert_eq!(accounts(store.bank()), expected);
+        assert_eq!(store.bank().get_account_money(&id).unwrap(), 15.);
+    }
+
+    #[test]
+    fn crash_before_log_is_emptied() {
+        let dir = tempdir().unwrap();
+        let id = Uuid::new_v4();
+        {
+            let mut store = Store::open(dir.path()).unwrap();
+            store
+                .add_account(Account::with_id::<Dollar>("account", &id))
+                .unwrap();
+            store.add_account_money(&id, 10.).unwrap();
+
+            let log = fs::read(dir.path().join(LOG_FILE)).unwrap();
+            store.snapshot().unwrap();
+            // The snapshot was written, but the log still holds its operations
+            fs::write(dir.path().join(LOG_FILE), log).unwrap();
+        }
+
+        let mut store = Store::open(dir.path()).unwrap();
+        assert_eq!(store.bank().get_account_money(&id).unwrap(), 10.);
+
+        store.add_account_money(&id, 1.).unwrap();
+        drop(store);
+        let store = Store::open(dir.path()).unwrap();
+        assert_eq!(store.bank().get_account_money(&id).unwrap(), 11.);
+    }
+
+    #[test]
+    fn requests_survive_reopen() {
+        let dir = tempdir().unwrap();
+        let id = Uuid::new_v4();
+        let deposit = Uuid::new_v4();
+        let withdrawal = Uuid::new_v4();
+        {
+            let mut store = Store::open(dir.path()).unwrap();
+            store
+                .add_account(Account::with_id::<Dollar>("account", &id))
+                .unwrap();
+            store
+                .add_account_money_idempotent(&deposit, &id, 10.)
+                .unwrap();
+            store.snapshot().unwrap();
+            store
+                .retrieve_account_money_idempotent(&withdrawal, &id, 4.)
+                .unwrap();
+        }
+
+        let mut store = Store::open(dir.path()).unwrap();
+        // One request comes from the snapshot, the other from the log
+        store
+            .add_account_money_idempotent(&deposit, &id, 10.)
+            .unwrap();
+        assert_eq!(
+            store
+                .retrieve_account_money_idempotent(&withdrawal, &id, 4.)
+                .unwrap(),
+            4.
+        );
+        assert_eq!(store.bank().get_account_money(&id).unwrap(), 6.);
+    }
+
+    #[test]
+    fn conversions_survive_reopen() {
+        let dir = tempdir().unwrap();
+        let id = Uuid::new_v4();
+        let expected = {
+            let mut store = Store::open(dir.path()).unwrap();
+            store
+                .add_account(Account::with_id::<Euro>("account", &id))
+                .unwrap();
+            store.add_account_money(&id, 117.).unwrap();
+            store.snapshot().unwrap();
+            store.retrieve_account_money(&id, 11.7).unwrap();
+            store
+                .bank()
+                .get_account(&id)
+                .unwrap()
+                .get_conversions()
+                .to_vec()
+        };
+
+        let store = Store::open(dir.path()).unwrap();
+        let conversions = store.bank().get_account(&id).unwrap().get_conversions();
+        assert_eq!(conversions.len(), 2);
+        assert_eq!(conversions, &expected[..]);
+    }
+
+    #[test]
+    fn months_follow_the_clock() {
+        let dir = tempdir().unwrap();
+        let id = Uuid::new_v4();
+        let clock = ManualClock::new(date(2021, 3, 31).unwrap());
+        let limit = Some(Limit {
+            amount: 100.,
+            enforcement: Enforcement::Block,
+        });
+        {
+            let mut store = Store::open_with_clock(dir.path(), clock.clone()).unwrap();
+            store
+                .add_account(Account::with_amount_and_id::<Dollar>("account", 500., &id))
+                .unwrap();
+            store.set_spending_limit(&id, None, limit).unwrap();
+            store.retrieve_account_money(&id, 100.).unwrap();
+            assert!(matches!(
+                store.retrieve_account_money(&id, 1.),
+                Err(StoreError::LimitExceeded(_))
+            ));
+            clock.advance(Duration::from_secs(24 * 60 * 60));
+            store.retrieve_account_money(&id, 100.).unwrap();
+        }
+
+        // Replayed at the times the operations were logged
+        let mut store = Store::open_with_clock(dir.path(), clock.clone()).unwrap();
+        assert_eq!(store.bank().get_account_money(&id).unwrap(), 300.);
+        let april = store.bank().budget_report(&id).unwrap();
+        assert_eq!(april[0].spent, 100.);
+        assert!(store.retrieve_account_money(&id, 1.).is_err());
+    }
+
+    #[test]
+    fn budgets_survive_reopen() {
+        let dir = tempdir().unwrap();
+        let id = Uuid::new_v4();
+        let limit = Some(Limit {
+            amount: 10.,
+            enforcement: Enforcement::Block,
+        });
+        let expected = {
+            let mut store = Store::open(dir.path()).unwrap();
+            store
+                .add_account(Account::with_amount_and_id::<Dollar>("account", 100., &id))
+                .unwrap();
+            store.set_spending_limit(&id, Some("food"), limit).unwrap();
+            store
+                .retrieve_account_money_for(&id, 6., Some("food"))
+                .unwrap();
+            store.snapshot().unwrap();
+            assert!(matches!(
+                store.retrieve_account_money_for(&id, 6., Some("food")),
+                Err(StoreError::LimitExceeded(_))
+            ));
+            store
+                .retrieve_account_money_for(&id, 3., Some("food"))
+                .unwrap();
+            store.bank().get_account(&id).unwrap().get_budget().clone()
+        };
+
+        let store = Store::open(dir.path()).unwrap();
+        let account = store.bank().get_account(&id).unwrap();
+        assert_eq!(account.get_budget(), &expected);
+        assert_eq!(account.get_budget().spending().len(), 2);
+        assert_eq!(account.get_value(), 91.);
+    }
+
+    #[test]
+    fn idempotency_window_survives_reopen() {
+        let dir = tempdir().unwrap();
+        let id = Uuid::new_v4();
+        let request = Uuid::new_v4();
+        {
+            let mut store = Store::open(dir.path()).unwrap();
+            store
+                .add_account(Account::with_id::<Dollar>("account", &id))
+                .unwrap();
+            store
+                .set_idempotency_window(Duration::from_secs(0))
+                .unwrap();
+            // Forgotten at once, so the retry is applied again
+            for _ in 0..2 {
+                store
+                    .add_account_money_idempotent(&request, &id, 10.)
+                    .unwrap();
+            }
+        }
+
+        let mut store = Store::open(dir.path()).unwrap();
+        assert_eq!(store.bank().get_account_money(&id).unwrap(), 20.);
+        store.snapshot().unwrap();
+        let store = Store::open(dir.path()).unwrap();
+        assert_eq!(store.bank().requests().window(), Duration::from_secs(0));
+    }
+
+    #[test]
+    fn added_account_keeps_its_state() {
+        let dir = tempdir().unwrap();
+        let id = Uuid::new_v4();
+        let mut account = Account::with_id::<Euro>("account", &id);
+        account.add_money(117.);
+        account.budget_mut().set_limit(
+            Some("food"),
+            Some(Limit {
+                amount: 10.,
+                enforcement: Enforcement::Warn,
+            }),
+        );
+        let conversions = account.get_conversions().to_vec();
+        let budget = account.get_budget().clone();
+        {
+            let mut store = Store::open(dir.path()).unwrap();
+            store.add_account(account).unwrap();
+        }
+
+        let store = Store::open(dir.path()).unwrap();
+        let account = store.bank().get_account(&id).unwrap();
+        assert_eq!(account.get_conversions(), &conversions[..]);
+        assert_eq!(account.get_budget(), &budget);
+        assert_eq!(account.get_value(), 117.);
+    }
+
+    #[test]
+    fn ledger_survives_reopen() {
+        let dir = tempdir().unwrap();
+        let dollars = Uuid::new_v4();
+        let euros = Uuid::new_v4();
+        let expected = {
+            let mut store = Store::open(dir.path()).unwrap();
+            store
+                .add_account(Account::with_amount_and_id::<Dollar>(
+                    "dollars", 100., &dollars,
+                ))
+                .unwrap();
+            store.enable_double_entry().unwrap();
+            store
+                .add_account(Account::with_id::<Euro>("euros", &euros))
+                .unwrap();
+            store.transfer(&dollars, &euros, 11.7).unwrap();
+            assert_eq!(store.charge_fee(&dollars, 2.).unwrap(), 2.);
+            store.snapshot().unwrap();
+            store.charge_fee(&euros, 1.17).unwrap();
+            store.bank().ledger().unwrap().postings().to_vec()
+        };
+
+        let store = Store::open(dir.path()).unwrap();
+        let ledger = store.bank().ledger().unwrap();
+        assert_eq!(ledger.postings(), &expected[..]);
+        assert_eq!(ledger.check(), Ok(()));
+        assert_eq!(ledger.balance(FEES, "USD"), -317);
+        assert_eq!(store.bank().get_account_money(&dollars).unwrap(), 86.3);
+    }
+
+    #[test]
+    fn rates_survive_reopen() {
+        let dir = tempdir().unwrap();
+        let id = Uuid::new_v4();
+        let day = |days: u64| SystemTime::UNIX_EPOCH + Duration::from_secs(days * 86400);
+        {
+            let mut store = Store::open(dir.path()).unwrap();
+            store
+                .add_account(Account::with_id::<Euro>("account", &id))
+                .unwrap();
+            let mut history = RateHistory::new();
+            history.insert("EUR", day(10), 1.2);
+            history.insert("EUR", day(20), 1.4);
+            store
+                .set_rate_history(history, Lookup::Interpolated)
+                .unwrap();
+            store.add_account_money_as_of(&id, 120., day(10)).unwrap();
+            store
+                .retrieve_account_money_as_of(&id, 13., day(15))
+                .unwrap();
+            assert!(matches!(
+                store.add_account_money_as_of(&id, 1., day(5)),
+                Err(StoreError::NoRate { .. })
+            ));
+        }
+
+        // Replayed from the log
+        let mut store = Store::open(dir.path()).unwrap();
+        let amount = store.bank().get_account(&id).unwrap().get_amount();
+        assert!((amount - 90.).abs() < 1e-9);
+        store.snapshot().unwrap();
+
+        let store = Store::open(dir.path()).unwrap();
+        assert_eq!(store.bank().rate_history().len(), 2);
+        assert_eq!(store.bank().rate_lookup(), Lookup::Interpolated);
+        let amount = store.bank().get_account(&id).unwrap().get_amount();
+        assert!((amount - 90.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn recover_torn_log() {
+        let dir = tempdir().unwrap();
+        let first = Uuid::new_v4();
+        let second = Uuid::new_v4();
+        let request = Uuid::new_v4();
+
+        // Expected state of the banck for each length of the log
+        let mut states = Vec::new();
+        {
+            let mut store = Store::open(dir.path()).unwrap();
+            states.push((log_len(dir.path()), accounts(store.bank())));
+
+            let operations: Vec<Step> = vec![
+                Box::new(|store| {
+                    store
+                        .add_account(Account::with_amount_and_id::<Dollar>("first", 10., &first))
+                        .unwrap()
+                }),
+                Box::new(|store| {
+                    store
+                        .add_account(Account::with_id::<Euro>("second", &second))
+                        .unwrap()
+                }),
+                Box::new(|store| store.add_account_money(&first, 5.).unwrap()),
+                Box::new(|store| {
+                    store
+                        .transfer_idempotent(&request, &first, &second, 3.)
+                        .unwrap();
+                }),
+                Box::new(|store| {
+                    store
+                        .transfer_idempotent(&request, &first, &second, 3.)
+                        .unwrap();
+                }),
+                Box::new(|store| {
+                    store.retrieve_account_money(&second, 1.).unwrap();
+                }),
+                Box::new(|store| store.rename_account(&first, "renamed").unwrap()),
+            ];
+            for operation in operations {
+                operation(&mut store);
+                states.push((log_len(dir.path()), accounts(store.bank())));
+            }
+        }
+
+        let log = fs::read(dir.path().join(LOG_FILE)).unwrap();
+        for offset in 0..=log.len() {
+            let crashed = tempdir().unwrap();
+            fs::write(crashed.path().join(LOG_FILE), &log[..offset]).unwrap();
+
+            let store = Store::open(crashed.path()).unwrap();
+            let (len, expected) = states
+                .iter()
+                .rev()
+                .find(|(len, _)| *len <= offset as u64)
+                .unwrap();
+            assert_eq!(&accounts(store.bank()), expected, "offset {}", offset);
+            assert_eq!(log_len(crashed.path()), *len, "offset {}", offset);
+        }
+    }
+
+    #[test]
+    fn recover_corrupted_tail() {
+        let dir = tempdir().unwrap();
+        let id = Uuid::new_v4();
+        let len = {
+            let mut store = Store::open(dir.path()).unwrap();
+            store
+                .add_account(Account::with_id::<Dollar>("account", &id))
+                .unwrap();
+            let len = log_len(dir.path());
+            store.add_account_money(&id, 10.).unwrap();
+            len
+        };
+
+        let mut log = fs::read(dir.path().join(LOG_FILE)).unwrap();
+        let last = log.len() - 2;
+        log[last] ^= 0xff;
+        fs::write(dir.path().join(LOG_FILE), log).unwrap();
+
+        let store = Store::open(dir.path()).unwrap();
+        assert_eq!(store.bank().get_account_money(&id).unwrap(), 0.);
+        assert_eq!(log_len(dir.path()), len);
+    }
+
+    #[test]
+    fn corrupted_record_before_the_tail() {
+        let dir = tempdir().unwrap();
+        let id = Uuid::new_v4();
+        {
+            let mut store = Store::open(dir.path()).unwrap();
+            store
+                .add_account(Account::with_id::<Dollar>("account", &id))
+                .unwrap();
+            store.add_account_money(&id, 10.).unwrap();
+        }
+
+        let mut log = fs::read(dir.path().join(LOG_FILE)).unwrap();
+        log[HEADER_SIZE + 1] ^= 0xff;
+        fs::write(dir.path().join(LOG_FILE), &log).unwrap();
+
+        assert!(matches!(
+            Store::open(dir.path()),
+            Err(StoreError::CorruptedLog(0))
+        ));
+        // The acknowledged records after the corrupted one are kept
+        assert_eq!(log_len(dir.path()), log.len() as u64);
+    }
+}